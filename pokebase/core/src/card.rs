@@ -4,6 +4,7 @@ use crate::set;
 
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
+use std::fmt;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Card {
@@ -24,6 +25,11 @@ impl Id {
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    // The number of the card within its set, as printed (e.g. "001" or "TG05")
+    pub fn number(&self) -> &str {
+        self.0.rsplit('-').next().unwrap_or(&self.0)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
@@ -80,3 +86,54 @@ pub struct Variants {
     pub reverse: bool,
     pub w_promo: bool,
 }
+
+impl fmt::Display for Rarity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::None => "",
+            Self::Common => "Common",
+            Self::Uncommon => "Uncommon",
+            Self::Rare => "Rare",
+            Self::HoloRare => "Holo Rare",
+            Self::HoloRareLvx => "Holo Rare LV.X",
+            Self::HoloRareV => "Holo Rare V",
+            Self::HoloRareVmax => "Holo Rare VMAX",
+            Self::HoloRareVstar => "Holo Rare VSTAR",
+            Self::ShinyRare => "Shiny Rare",
+            Self::ShinyRareV => "Shiny Rare V",
+            Self::ShinyRareVmax => "Shiny Rare VMAX",
+            Self::DoubleRare => "Double Rare",
+            Self::AceSpecRare => "ACE SPEC Rare",
+            Self::AmazingRare => "Amazing Rare",
+            Self::RadiantRare => "Radiant Rare",
+            Self::RarePrime => "Rare Prime",
+            Self::Legend => "LEGEND",
+            Self::ClassicCollection => "Classic Collection",
+            Self::UltraRare => "Ultra Rare",
+            Self::ShinyUltraRare => "Shiny Ultra Rare",
+            Self::SecretRare => "Secret Rare",
+            Self::FullArtTrainer => "Full Art Trainer",
+            Self::IllustrationRare => "Illustration Rare",
+            Self::SpecialIllustrationRare => "Special Illustration Rare",
+            Self::HyperRare => "Hyper Rare",
+        })
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Grass => "Grass",
+            Self::Fire => "Fire",
+            Self::Water => "Water",
+            Self::Lightning => "Lightning",
+            Self::Psychic => "Psychic",
+            Self::Fighting => "Fighting",
+            Self::Darkness => "Darkness",
+            Self::Metal => "Metal",
+            Self::Fairy => "Fairy",
+            Self::Dragon => "Dragon",
+            Self::Colorless => "Colorless",
+        })
+    }
+}
@@ -1,4 +1,5 @@
 use crate::card;
+use crate::kana;
 use crate::locale;
 use crate::pokemon;
 use crate::series;
@@ -7,9 +8,10 @@ use crate::{Card, Locale, Map, Pokemon, Series, Set};
 
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt;
 use std::path::Path;
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct Database {
@@ -17,9 +19,33 @@ pub struct Database {
     pub series: Map<series::Id, Series>,
     pub sets: Map<set::Id, Set>,
     pub cards: Map<card::Id, Card>,
+    // Every name in the database, romanized once for searching
+    pub romaji: Arc<HashMap<String, String>>,
 }
 
 impl Database {
+    pub fn new(
+        pokemon: Vec<Pokemon>,
+        series: Vec<Series>,
+        sets: Vec<Set>,
+        cards: Vec<Card>,
+    ) -> Self {
+        let romaji = pokemon
+            .iter()
+            .flat_map(|pokemon| pokemon.name.values())
+            .chain(cards.iter().flat_map(|card| card.name.values()))
+            .map(|name| (name.clone(), kana::romanize(name)))
+            .collect();
+
+        Self {
+            pokemon: Map::new(pokemon, |pokemon| pokemon.id),
+            series: Map::new(series, |series| series.id.clone()),
+            sets: Map::new(sets, |set| set.id.clone()),
+            cards: Map::new(cards, |card| card.id.clone()),
+            romaji: Arc::new(romaji),
+        }
+    }
+
     pub async fn load() -> Result<Self, anywho::Error> {
         use tokio::task;
 
@@ -31,12 +57,7 @@ impl Database {
 
             log::info!("Loaded database with {} cards", cards.len());
 
-            Self {
-                pokemon: Map::new(pokemon, |pokemon| pokemon.id),
-                series: Map::new(series, |series| series.id.clone()),
-                sets: Map::new(sets, |set| set.id.clone()),
-                cards: Map::new(cards, |card| card.id.clone()),
-            }
+            Self::new(pokemon, series, sets, cards)
         })
         .await?)
     }
@@ -242,12 +263,7 @@ impl Database {
         let mut sets: Vec<_> = sets.into_values().collect();
         sets.sort_by(|a, b| a.release_date.cmp(&b.release_date));
 
-        Ok(Self {
-            pokemon: Map::new(pokemon, |pokemon| pokemon.id),
-            series: Map::new(series, |series| series.id.clone()),
-            sets: Map::new(sets, |set| set.id.clone()),
-            cards: Map::new(cards, |card| card.id.clone()),
-        })
+        Ok(Self::new(pokemon, series, sets, cards))
     }
 }
 
@@ -0,0 +1,199 @@
+pub fn to_romaji(text: &str) -> String {
+    let mut romaji = String::with_capacity(text.len() * 2);
+    let mut geminate = false;
+
+    for c in text.chars() {
+        let c = to_hiragana(c);
+
+        match c {
+            // Sokuon doubles the next consonant
+            'っ' => {
+                geminate = true;
+                continue;
+            }
+            // Chōonpu extends the previous vowel
+            'ー' => {
+                if let Some(vowel) = romaji.chars().next_back().filter(|c| is_vowel(*c)) {
+                    romaji.push(vowel);
+                }
+            }
+            'ゃ' | 'ゅ' | 'ょ' => {
+                let small = match c {
+                    'ゃ' => "a",
+                    'ゅ' => "u",
+                    _ => "o",
+                };
+
+                // Yōon: "ki" + "ya" = "kya", "shi" + "ya" = "sha", etc.
+                if romaji.ends_with("shi") || romaji.ends_with("chi") || romaji.ends_with("ji") {
+                    let _ = romaji.pop();
+                } else if romaji.ends_with('i') {
+                    let _ = romaji.pop();
+                    romaji.push('y');
+                } else {
+                    romaji.push('y');
+                }
+
+                romaji.push_str(small);
+            }
+            'ぁ' | 'ぃ' | 'ぅ' | 'ぇ' | 'ぉ' | 'ゎ' => {
+                let small = match c {
+                    'ぁ' | 'ゎ' => 'a',
+                    'ぃ' => 'i',
+                    'ぅ' => 'u',
+                    'ぇ' => 'e',
+                    _ => 'o',
+                };
+
+                // Extended katakana: "fu" + "a" = "fa", "te" + "i" = "ti", etc.
+                match romaji.chars().next_back() {
+                    Some(last) if is_vowel(last) => {
+                        let _ = romaji.pop();
+
+                        match romaji.chars().next_back() {
+                            Some(previous) if !is_vowel(previous) && previous != 'n' => {}
+                            _ => romaji.push(if last == 'i' { 'y' } else { 'w' }),
+                        }
+                    }
+                    _ => {}
+                }
+
+                romaji.push(small);
+            }
+            _ => {
+                if let Some(syllable) = syllable(c) {
+                    if std::mem::take(&mut geminate) {
+                        if syllable.starts_with("ch") {
+                            romaji.push('t');
+                        } else if let Some(consonant) =
+                            syllable.chars().next().filter(|c| !is_vowel(*c))
+                        {
+                            romaji.push(consonant);
+                        }
+                    }
+
+                    romaji.push_str(syllable);
+                } else {
+                    romaji.extend(c.to_lowercase());
+                }
+            }
+        }
+
+        geminate = false;
+    }
+
+    romaji
+}
+
+pub fn romanize(text: &str) -> String {
+    let romaji = to_romaji(text);
+    let mut normalized = String::with_capacity(romaji.len());
+
+    // Long vowels are ignored, so "pikachu" matches "pikachuu"
+    for c in romaji.chars() {
+        match (normalized.chars().next_back(), c) {
+            (Some('a'), 'a') | (Some('i'), 'i') | (Some('u'), 'u') | (Some('e'), 'e') => {}
+            (Some('o'), 'o' | 'u') => {}
+            _ => normalized.push(c),
+        }
+    }
+
+    normalized
+}
+
+pub fn is_kana(text: &str) -> bool {
+    text.chars().any(|c| syllable(to_hiragana(c)).is_some())
+}
+
+fn to_hiragana(c: char) -> char {
+    match c {
+        'ァ'..='ヶ' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+        _ => c,
+    }
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'i' | 'u' | 'e' | 'o')
+}
+
+fn syllable(c: char) -> Option<&'static str> {
+    Some(match c {
+        'あ' => "a",
+        'い' => "i",
+        'う' => "u",
+        'え' => "e",
+        'お' => "o",
+        'か' | 'ゕ' => "ka",
+        'き' => "ki",
+        'く' => "ku",
+        'け' | 'ゖ' => "ke",
+        'こ' => "ko",
+        'が' => "ga",
+        'ぎ' => "gi",
+        'ぐ' => "gu",
+        'げ' => "ge",
+        'ご' => "go",
+        'さ' => "sa",
+        'し' => "shi",
+        'す' => "su",
+        'せ' => "se",
+        'そ' => "so",
+        'ざ' => "za",
+        'じ' | 'ぢ' => "ji",
+        'ず' | 'づ' => "zu",
+        'ぜ' => "ze",
+        'ぞ' => "zo",
+        'た' => "ta",
+        'ち' => "chi",
+        'つ' => "tsu",
+        'て' => "te",
+        'と' => "to",
+        'だ' => "da",
+        'で' => "de",
+        'ど' => "do",
+        'な' => "na",
+        'に' => "ni",
+        'ぬ' => "nu",
+        'ね' => "ne",
+        'の' => "no",
+        'は' => "ha",
+        'ひ' => "hi",
+        'ふ' => "fu",
+        'へ' => "he",
+        'ほ' => "ho",
+        'ば' => "ba",
+        'び' => "bi",
+        'ぶ' => "bu",
+        'べ' => "be",
+        'ぼ' => "bo",
+        'ぱ' => "pa",
+        'ぴ' => "pi",
+        'ぷ' => "pu",
+        'ぺ' => "pe",
+        'ぽ' => "po",
+        'ま' => "ma",
+        'み' => "mi",
+        'む' => "mu",
+        'め' => "me",
+        'も' => "mo",
+        'や' => "ya",
+        'ゆ' => "yu",
+        'よ' => "yo",
+        'ら' => "ra",
+        'り' => "ri",
+        'る' => "ru",
+        'れ' => "re",
+        'ろ' => "ro",
+        'わ' => "wa",
+        'ゐ' => "i",
+        'ゑ' => "e",
+        'を' => "o",
+        'ん' => "n",
+        'ゔ' => "vu",
+        'ヷ' => "va",
+        'ヸ' => "vi",
+        'ヹ' => "ve",
+        'ヺ' => "vo",
+        _ => return None,
+    })
+}
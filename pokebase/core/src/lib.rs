@@ -2,6 +2,7 @@ mod database;
 mod map;
 
 pub mod card;
+pub mod kana;
 pub mod locale;
 pub mod pokemon;
 pub mod series;
@@ -63,6 +63,10 @@ impl<T> Map<T> {
         self.0.keys()
     }
 
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.0.values()
+    }
+
     pub fn as_str(&self) -> &str
     where
         T: AsRef<str>,
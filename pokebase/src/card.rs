@@ -2,22 +2,38 @@ pub mod pricing;
 
 pub use crate::core::card::*;
 
+use crate::kana;
+use crate::locale;
+use crate::session::{Conditional, Validator};
 use crate::{Database, Error, Session};
 
 use bytes::Bytes;
+use std::collections::BTreeSet;
 use std::fmt;
 use std::sync::mpsc;
 use std::sync::{Arc, LazyLock};
 
 #[derive(Clone)]
 pub struct Image {
+    pub resolution: Resolution,
     pub bytes: Bytes,
+    pub provider: &'static str,
+    pub validator: Validator,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Resolution {
+    Low,
+    High,
 }
 
 impl fmt::Debug for Image {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Image")
+            .field("resolution", &self.resolution)
             .field("bytes", &self.bytes.len())
+            .field("provider", &self.provider)
+            .field("validator", &self.validator)
             .finish()
     }
 }
@@ -27,50 +43,162 @@ impl Image {
         card: &Card,
         database: &Database,
         session: &Session,
+        resolution: Resolution,
+        locale: Option<&str>,
     ) -> Result<Self, Error> {
-        use futures_util::TryFutureExt;
-
-        let download_from_pokemontcg = session.pokemon_tcg.download_image(card);
-        let download_from_tcgdex = session.tcgdex.download_image(card, database);
+        let mut last_error = None;
+        let mut is_missing = true;
+
+        for provider in &session.image_providers {
+            match provider
+                .download_image(card, database, resolution, locale, &Validator::default())
+                .await
+            {
+                Ok(Conditional::Modified(bytes, validator)) => {
+                    return Ok(Self {
+                        resolution,
+                        bytes,
+                        provider: provider.name(),
+                        validator,
+                    });
+                }
+                Ok(Conditional::NotModified) => {
+                    log::warn!(
+                        "{provider}: not modified without validator",
+                        provider = provider.name()
+                    );
+                }
+                Err(error) => {
+                    log::warn!("{provider}: {error}", provider = provider.name());
 
-        // Rationale on the order of image fetching:
-        // 1. PokemonTCG - Highest quality, but English only. 20,000 requests/day with an API key.
-        // 2. TCGdex - Lower quality, but supports multiple locales. Rate limiting unknown (?).
-        let bytes = download_from_pokemontcg
-            .or_else(|error| {
-                log::warn!("{error}");
+                    is_missing &= error.is_not_found();
+                    last_error = Some(error);
+                }
+            }
+        }
 
-                download_from_tcgdex
-            })
-            .await?;
+        match last_error {
+            Some(_) if is_missing => Err(Error::NotAvailable),
+            Some(error) => Err(error),
+            None => Err(Error::NoImageProviders),
+        }
+    }
 
-        Ok(Self { bytes })
+    // Returns `None` if the image has not changed since it was validated
+    pub async fn revalidate(
+        card: &Card,
+        database: &Database,
+        session: &Session,
+        resolution: Resolution,
+        locale: Option<&str>,
+        provider: &str,
+        validator: &Validator,
+    ) -> Result<Option<Self>, Error> {
+        let Some(provider) = session.image_provider(provider) else {
+            return Self::download(card, database, session, resolution, locale)
+                .await
+                .map(Some);
+        };
+
+        match provider
+            .download_image(card, database, resolution, locale, validator)
+            .await?
+        {
+            Conditional::Modified(bytes, validator) => Ok(Some(Self {
+                resolution,
+                bytes,
+                provider: provider.name(),
+                validator,
+            })),
+            Conditional::NotModified => Ok(None),
+        }
     }
 }
 
 pub fn search<'a>(query: &str, database: &Database) -> impl Future<Output = Search> + 'a {
     use tokio::task;
 
-    let query = query.to_lowercase();
+    let query = Query::new(query);
     let database = database.clone();
 
     async move {
         let mut matches = Vec::new();
+        let mut pokedex = BTreeSet::new();
+
+        if !query.is_empty() {
+            for pokemon in database.pokemon.values() {
+                if query.matches(&pokemon.name, &database) {
+                    let _ = pokedex.insert(pokemon.id);
+                }
+            }
+        }
 
         for card in database.cards.values().iter().rev() {
             if !card.name.is_supported() {
+                matches.push(false);
                 continue;
             }
 
-            if card.name.contains(&query) {
-                matches.push(card.clone());
+            let is_match = query.matches(&card.name, &database);
+
+            // Link names across locales through the Pokédex
+            if is_match && !query.is_empty() && card.pokedex.len() == 1 {
+                let _ = pokedex.insert(card.pokedex[0]);
             }
 
+            matches.push(is_match);
+
             // Avoid blocking
             task::yield_now().await;
         }
 
-        Search::new(matches)
+        let mut cards = Vec::new();
+
+        for (card, is_match) in database.cards.values().iter().rev().zip(matches) {
+            let is_linked = card.name.is_supported()
+                && card.pokedex.iter().any(|pokemon| pokedex.contains(pokemon));
+
+            if is_match || is_linked {
+                cards.push(card.clone());
+            }
+        }
+
+        Search::new(cards)
+    }
+}
+
+struct Query {
+    text: String,
+    romaji: String,
+    is_kana: bool,
+}
+
+impl Query {
+    fn new(query: &str) -> Self {
+        let text = query.to_lowercase();
+        let romaji = kana::romanize(&text);
+        let is_kana = kana::is_kana(&text);
+
+        Self {
+            text,
+            romaji,
+            is_kana,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    fn matches(&self, name: &locale::Map, database: &Database) -> bool {
+        name.contains(&self.text)
+            || name.values().any(|name| {
+                (self.is_kana || kana::is_kana(name))
+                    && database
+                        .romaji
+                        .get(name)
+                        .is_some_and(|romaji| romaji.contains(&self.romaji))
+            })
     }
 }
 
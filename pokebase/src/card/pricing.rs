@@ -1,26 +1,83 @@
 pub use crate::session::pokemon_tcg::cardmarket;
 pub use crate::session::pokemon_tcg::tcgplayer;
 
-use crate::{Card, Result, Session};
+use crate::card;
+use crate::session::{Conditional, Validator};
+use crate::set;
+use crate::{Card, Database, Error, Result, Session};
 
 use std::time::SystemTime;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct Pricing {
     pub tcgplayer: tcgplayer::Pricing,
     pub cardmarket: cardmarket::Pricing,
     pub updated_at: SystemTime,
+    pub validator: Validator,
 }
 
 impl Pricing {
     pub async fn fetch(card: &Card, session: &Session) -> Result<Self> {
-        let pricing = session.pokemon_tcg.fetch_pricing(card).await?;
+        Ok(Self::revalidate(card, session, &Validator::default())
+            .await?
+            .unwrap_or_default())
+    }
 
-        Ok(Self {
-            tcgplayer: pricing.tcgplayer,
-            cardmarket: pricing.cardmarket,
-            updated_at: SystemTime::now(),
-        })
+    // Returns `None` if the pricing has not changed since it was validated
+    pub async fn revalidate(
+        card: &Card,
+        session: &Session,
+        validator: &Validator,
+    ) -> Result<Option<Self>> {
+        // TCGdex covers the cards PokemonTCG does not know about (e.g. Japanese ones)
+        let pricing = match session.pokemon_tcg.fetch_pricing(card, validator).await {
+            Err(Error::LocaleNotAvailable) => session.tcgdex.fetch_pricing(card, validator).await?,
+            Err(Error::RequestFailed(error))
+                if error.status() == Some(reqwest::StatusCode::NOT_FOUND) =>
+            {
+                log::warn!("PokemonTCG: {error}");
+
+                session.tcgdex.fetch_pricing(card, validator).await?
+            }
+            pricing => pricing?,
+        };
+
+        match pricing {
+            Conditional::Modified(pricing, validator) => Ok(Some(Self {
+                tcgplayer: pricing.tcgplayer,
+                cardmarket: pricing.cardmarket,
+                updated_at: SystemTime::now(),
+                validator,
+            })),
+            Conditional::NotModified => Ok(None),
+        }
+    }
+
+    pub async fn fetch_set(
+        set: &set::Id,
+        database: &Database,
+        session: &Session,
+    ) -> Result<Vec<(card::Id, Self)>> {
+        let updated_at = SystemTime::now();
+
+        let prices = session.pokemon_tcg.fetch_set_pricing(set, database).await?;
+
+        Ok(prices
+            .into_iter()
+            .map(|(card, pricing)| {
+                (
+                    card,
+                    Self {
+                        tcgplayer: pricing.tcgplayer,
+                        cardmarket: pricing.cardmarket,
+                        updated_at,
+                        // Only a single card has a validator; keep the one
+                        // you had, if any
+                        validator: Validator::default(),
+                    },
+                )
+            })
+            .collect())
     }
 }
 
@@ -30,6 +87,7 @@ impl Default for Pricing {
             tcgplayer: tcgplayer::Pricing::default(),
             cardmarket: cardmarket::Pricing::default(),
             updated_at: SystemTime::now(),
+            validator: Validator::default(),
         }
     }
 }
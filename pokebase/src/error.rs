@@ -10,6 +10,49 @@ pub enum Error {
     SetNotFound(set::Id),
     #[error("locale is not available")]
     LocaleNotAvailable,
+    #[error("no image providers are enabled")]
+    NoImageProviders,
+    #[error("not available from any provider")]
+    NotAvailable,
+    #[error("daily quota of {0} is exhausted")]
+    QuotaExhausted(&'static str),
+    #[error("exchange rate not available: {0}")]
+    ExchangeRateNotAvailable(String),
+}
+
+impl Error {
+    pub fn is_retryable(&self) -> bool {
+        use reqwest::StatusCode;
+
+        match self {
+            Self::RequestFailed(error) => match error.status() {
+                Some(status) => {
+                    status == StatusCode::TOO_MANY_REQUESTS
+                        || status == StatusCode::REQUEST_TIMEOUT
+                        || status.is_server_error()
+                }
+                None => error.is_timeout() || error.is_connect() || error.is_body(),
+            },
+            Self::SetNotFound(_)
+            | Self::LocaleNotAvailable
+            | Self::NoImageProviders
+            | Self::NotAvailable
+            | Self::QuotaExhausted(_)
+            | Self::ExchangeRateNotAvailable(_) => false,
+        }
+    }
+
+    // Whether asking again later is pointless (for a while, at least)
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            Self::RequestFailed(error) => error.status() == Some(reqwest::StatusCode::NOT_FOUND),
+            Self::LocaleNotAvailable | Self::NotAvailable => true,
+            Self::SetNotFound(_)
+            | Self::NoImageProviders
+            | Self::QuotaExhausted(_)
+            | Self::ExchangeRateNotAvailable(_) => false,
+        }
+    }
 }
 
 impl From<reqwest::Error> for Error {
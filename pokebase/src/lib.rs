@@ -1,5 +1,6 @@
 use pokebase_core as core;
 
+pub use crate::core::kana;
 pub use crate::core::locale;
 pub use crate::core::pokemon;
 pub use crate::core::series;
@@ -1,28 +1,210 @@
+pub mod frankfurter;
+pub mod limiter;
 pub mod pokemon_tcg;
 pub mod tcgdex;
 
+pub use limiter::{Limiter, Priority};
+
+use frankfurter::Frankfurter;
 use pokemon_tcg::PokemonTcg;
 use tcgdex::Tcgdex;
 
+use crate::card::Resolution;
+use crate::{Card, Database, Error};
+
+use bytes::Bytes;
+use futures_util::future::BoxFuture;
+use reqwest::header;
+use serde::{Deserialize, Serialize};
 use std::fmt;
-use std::sync::LazyLock;
-use std::time::Duration;
+use std::path::PathBuf;
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, SystemTime};
+use tokio::time;
 
 #[derive(Debug, Clone)]
 pub struct Session {
     pub pokemon_tcg: PokemonTcg,
     pub tcgdex: Tcgdex,
+    pub frankfurter: Frankfurter,
+    pub image_providers: Vec<Arc<dyn ImageProvider>>,
 }
 
 impl Session {
     pub fn new(pokemon_tcg_api_key: Option<String>) -> Self {
+        Self::with_config(Config {
+            pokemon_tcg_api_key,
+            ..Config::default()
+        })
+    }
+
+    pub fn with_config(config: Config) -> Self {
+        let pokemon_tcg = PokemonTcg::new(&config);
+        let tcgdex = Tcgdex::new(&config);
+        let frankfurter = Frankfurter::new(&config);
+
+        // Rationale on the default order of image providers:
+        // 1. PokemonTCG - Highest quality, but English only. 20,000 requests/day with an API key.
+        // 2. TCGdex - Lower quality, but supports multiple locales. Rate limiting unknown (?).
+        let image_providers: Vec<Arc<dyn ImageProvider>> =
+            vec![Arc::new(pokemon_tcg.clone()), Arc::new(tcgdex.clone())];
+
+        Self {
+            pokemon_tcg,
+            tcgdex,
+            frankfurter,
+            image_providers,
+        }
+    }
+
+    pub fn background(&self) -> Self {
+        let pokemon_tcg = self.pokemon_tcg.background();
+        let tcgdex = self.tcgdex.background();
+
+        // Keep the order (and any removals) of the image providers, but at background priority
+        let image_providers = self
+            .image_providers
+            .iter()
+            .map(|provider| -> Arc<dyn ImageProvider> {
+                match provider.name() {
+                    PokemonTcg::NAME => Arc::new(pokemon_tcg.clone()),
+                    Tcgdex::NAME => Arc::new(tcgdex.clone()),
+                    _ => provider.clone(),
+                }
+            })
+            .collect();
+
+        Self {
+            pokemon_tcg,
+            tcgdex,
+            frankfurter: self.frankfurter.clone(),
+            image_providers,
+        }
+    }
+
+    pub fn image_provider(&self, name: &str) -> Option<&Arc<dyn ImageProvider>> {
+        self.image_providers
+            .iter()
+            .find(|provider| provider.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub pokemon_tcg_api_key: Option<String>,
+    pub endpoints: Endpoints,
+    pub quotas: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoints {
+    pub pokemon_tcg_api: String,
+    pub pokemon_tcg_images: String,
+    pub tcgdex_api: String,
+    pub tcgdex_assets: String,
+    pub exchange_rates: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
         Self {
-            pokemon_tcg: PokemonTcg::new(pokemon_tcg_api_key),
-            tcgdex: Tcgdex::new(),
+            pokemon_tcg_api: "https://api.pokemontcg.io/v2".to_owned(),
+            pokemon_tcg_images: "https://images.pokemontcg.io".to_owned(),
+            tcgdex_api: "https://api.tcgdex.net/v2".to_owned(),
+            tcgdex_assets: "https://assets.tcgdex.net".to_owned(),
+            exchange_rates: "https://api.frankfurter.app".to_owned(),
         }
     }
 }
 
+pub trait ImageProvider: fmt::Debug + Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn download_image<'a>(
+        &'a self,
+        card: &'a Card,
+        database: &'a Database,
+        resolution: Resolution,
+        locale: Option<&'a str>,
+        validator: &'a Validator,
+    ) -> BoxFuture<'a, Result<Conditional<Bytes>, Error>>;
+}
+
+// The cache validators of a resource, as returned by the server
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Validator {
+    // The provider that issued the validators; they mean nothing to any other
+    #[serde(default)]
+    pub provider: Option<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl Validator {
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+
+    fn from_response(provider: &str, response: &reqwest::Response) -> Self {
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned)
+        };
+
+        Self {
+            provider: Some(provider.to_owned()),
+            etag: header(header::ETAG),
+            last_modified: header(header::LAST_MODIFIED),
+        }
+    }
+
+    fn apply(&self, provider: &str, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        if self.provider.as_deref() != Some(provider) {
+            return request;
+        }
+
+        let request = if let Some(etag) = &self.etag {
+            request.header(header::IF_NONE_MATCH, etag)
+        } else {
+            request
+        };
+
+        if let Some(last_modified) = &self.last_modified {
+            request.header(header::IF_MODIFIED_SINCE, last_modified)
+        } else {
+            request
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Conditional<T> {
+    Modified(T, Validator),
+    NotModified,
+}
+
+impl<T> Conditional<T> {
+    async fn from_response<F>(
+        provider: &str,
+        response: reqwest::Response,
+        f: impl FnOnce(reqwest::Response) -> F,
+    ) -> Result<Self, Error>
+    where
+        F: Future<Output = Result<T, Error>>,
+    {
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(Self::NotModified);
+        }
+
+        let validator = Validator::from_response(provider, &response);
+
+        Ok(Self::Modified(f(response).await?, validator))
+    }
+}
+
 static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
     reqwest::ClientBuilder::new()
         .timeout(Duration::from_secs(3))
@@ -30,29 +212,230 @@ static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
         .expect("Build reqwest client")
 });
 
-async fn retry<T, E, F>(mut retries: usize, f: impl Fn() -> F) -> Result<T, E>
+async fn retry<F>(mut retries: usize, f: impl Fn() -> F) -> Result<reqwest::Response, Error>
 where
-    E: fmt::Display,
-    F: Future<Output = Result<T, E>>,
+    F: Future<Output = Result<reqwest::Response, Error>>,
 {
-    loop {
-        let result = f().await;
+    // Waiting any longer than this is not worth it; we will try again later
+    const MAX_DELAY: Duration = Duration::from_secs(60);
 
-        match result {
+    let mut attempt = 0;
+
+    loop {
+        let (error, retry_after) = match f().await {
             Ok(response) => {
-                break Ok(response);
-            }
-            Err(error) => {
-                if retries > 0 {
-                    log::warn!(
-                        "{error} ({retries} {} left)",
-                        if retries == 1 { "retry" } else { "retries" }
-                    );
-                    retries -= 1;
-                } else {
-                    break Err(error);
+                let retry_after = retry_after(&response);
+
+                match response.error_for_status() {
+                    Ok(response) => break Ok(response),
+                    Err(error) => (Error::from(error), retry_after),
                 }
             }
+            Err(error) => (error, None),
+        };
+
+        if retries == 0 || !error.is_retryable() {
+            break Err(error);
+        }
+
+        let delay = retry_after.unwrap_or_else(|| backoff(attempt));
+
+        if delay > MAX_DELAY {
+            log::warn!("{error} (retry in {delay:?} is too far away)");
+            break Err(error);
+        }
+
+        log::warn!(
+            "{error} (retrying in {delay:?}; {retries} {} left)",
+            if retries == 1 { "retry" } else { "retries" }
+        );
+
+        time::sleep(delay).await;
+
+        retries -= 1;
+        attempt += 1;
+    }
+}
+
+fn backoff(attempt: u32) -> Duration {
+    const BASE: Duration = Duration::from_millis(500);
+    const MAX: Duration = Duration::from_secs(10);
+
+    let delay = BASE.saturating_mul(1 << attempt.min(16)).min(MAX);
+
+    // "Equal jitter" keeps at least half of the delay
+    delay.mul_f64(0.5 + fastrand::f64() * 0.5)
+}
+
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?;
+
+    parse_retry_after(retry_after, SystemTime::now())
+}
+
+// Either delay-seconds or an HTTP-date
+fn parse_retry_after(retry_after: &str, now: SystemTime) -> Option<Duration> {
+    let retry_after = retry_after.trim();
+
+    if let Ok(seconds) = retry_after.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    parse_http_date(retry_after)?
+        .duration_since(now)
+        .ok()
+        .or(Some(Duration::ZERO))
+}
+
+// Parses an IMF-fixdate (e.g. "Sun, 06 Nov 1994 08:49:37 GMT") and the obsolete
+// RFC 850 (e.g. "Sunday, 06-Nov-94 08:49:37 GMT") and asctime (e.g. "Sun Nov  6 08:49:37 1994")
+// formats, which recipients must still accept
+fn parse_http_date(date: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let parts: Vec<&str> = date.split_whitespace().skip(1).collect();
+
+    let (day, month, year, time) = match parts.as_slice() {
+        [day, month, year, time, "GMT"] => (*day, *month, *year, *time),
+        [date, time, "GMT"] => {
+            let mut date = date.split('-');
+            let date = (date.next()?, date.next()?, date.next()?);
+
+            (date.0, date.1, date.2, *time)
         }
+        [month, day, time, year] => (*day, *month, *year, *time),
+        _ => return None,
+    };
+
+    let day: i64 = day.parse().ok()?;
+    let month = MONTHS.iter().position(|candidate| *candidate == month)? as i64 + 1;
+    let year = match (year.len(), year.parse::<i64>().ok()?) {
+        // Two-digit years of RFC 850; close enough until 2070
+        (2, year @ 0..70) => year + 2000,
+        (2, year) => year + 1900,
+        (_, year) => year,
+    };
+
+    let mut time = time.split(':').map(|part| part.parse::<i64>().ok());
+    let hours = time.next()??;
+    let minutes = time.next()??;
+    let seconds = time.next()??;
+
+    // Days since the UNIX epoch; courtesy of Howard Hinnant's `days_from_civil`
+    let days = {
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+        era * 146_097 + day_of_era - 719_468
+    };
+
+    let timestamp = days * 86_400 + hours * 3_600 + minutes * 60 + seconds;
+
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(u64::try_from(timestamp).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sun, 06 Nov 1994 08:49:37 GMT
+    const TIMESTAMP: u64 = 784_111_777;
+
+    fn at(seconds: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn imf_fixdate_is_parsed() {
+        assert_eq!(
+            parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(at(TIMESTAMP))
+        );
+        assert_eq!(
+            parse_http_date("Thu, 01 Jan 1970 00:00:00 GMT"),
+            Some(at(0))
+        );
+        assert_eq!(
+            parse_http_date("Tue, 29 Feb 2028 23:59:59 GMT"),
+            Some(at(1_835_481_599))
+        );
+    }
+
+    #[test]
+    fn rfc_850_date_is_parsed() {
+        assert_eq!(
+            parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"),
+            Some(at(TIMESTAMP))
+        );
+        assert_eq!(
+            parse_http_date("Tuesday, 29-Feb-28 23:59:59 GMT"),
+            Some(at(1_835_481_599))
+        );
+    }
+
+    #[test]
+    fn asctime_date_is_parsed() {
+        assert_eq!(
+            parse_http_date("Sun Nov  6 08:49:37 1994"),
+            Some(at(TIMESTAMP))
+        );
+    }
+
+    #[test]
+    fn invalid_date_is_rejected() {
+        assert_eq!(parse_http_date(""), None);
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 PST"), None);
+        assert_eq!(parse_http_date("Sun, 06 Foo 1994 08:49:37 GMT"), None);
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49 GMT"), None);
+        assert_eq!(parse_http_date("Sunday, 06-Nov 08:49:37 GMT"), None);
+    }
+
+    #[test]
+    fn retry_after_is_parsed() {
+        let now = at(TIMESTAMP);
+
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(parse_retry_after(" 0 ", now), Some(Duration::ZERO));
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 1994 08:50:37 GMT", now),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 1994 08:48:37 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("-1", now), None);
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn backoff_is_bounded() {
+        for attempt in 0..64 {
+            let expected = Duration::from_millis(500)
+                .saturating_mul(1 << attempt.min(16))
+                .min(Duration::from_secs(10));
+
+            for _ in 0..100 {
+                let delay = backoff(attempt);
+
+                assert!(delay >= expected / 2, "{attempt}: {delay:?}");
+                assert!(delay <= expected, "{attempt}: {delay:?}");
+            }
+        }
+
+        assert!(backoff(0) <= Duration::from_millis(500));
+        assert!(backoff(u32::MAX) <= Duration::from_secs(10));
     }
 }
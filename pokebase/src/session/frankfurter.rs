@@ -0,0 +1,40 @@
+use crate::Error;
+use crate::session::{self, Config};
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone)]
+pub struct Frankfurter {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl Frankfurter {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            client: session::CLIENT.clone(),
+            url: config.endpoints.exchange_rates.clone(),
+        }
+    }
+
+    pub async fn fetch_rate(&self, from: &str, to: &str) -> Result<f64, Error> {
+        let url = format!("{url}/latest?from={from}&to={to}", url = self.url);
+
+        #[derive(Deserialize)]
+        struct Response {
+            rates: BTreeMap<String, f64>,
+        }
+
+        log::info!("Fetching exchange rate: {url}");
+
+        let response = session::retry(2, async || Ok(self.client.get(&url).send().await?)).await?;
+        let response: Response = response.json().await?;
+
+        response
+            .rates
+            .get(to)
+            .copied()
+            .ok_or_else(|| Error::ExchangeRateNotAvailable(format!("{from}/{to}")))
+    }
+}
@@ -0,0 +1,234 @@
+use crate::Error;
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::fs;
+use tokio::time;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limit {
+    pub burst: u32,
+    pub per_second: f64,
+    pub daily: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Priority {
+    Background,
+    #[default]
+    Foreground,
+}
+
+#[derive(Debug, Clone)]
+pub struct Limiter {
+    name: &'static str,
+    limit: Limit,
+    path: Option<PathBuf>,
+    state: Arc<Mutex<State>>,
+    saving: Arc<tokio::sync::Mutex<()>>,
+}
+
+#[derive(Debug)]
+struct State {
+    tokens: f64,
+    refilled_at: Instant,
+    quota: Quota,
+    is_loaded: bool,
+    unsaved: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct Quota {
+    day: u64,
+    used: u32,
+}
+
+impl Limiter {
+    // Background work stops when less than 10% of the daily quota is left
+    const RESERVE: f64 = 0.1;
+
+    // Persisting every request would be wasteful; losing a few is fine
+    const SAVE_EVERY: u32 = 10;
+
+    pub fn new(name: &'static str, limit: Limit, quotas: Option<&Path>) -> Self {
+        let path = quotas.map(|quotas| quotas.join(format!("{}.ron", name.to_lowercase())));
+
+        let is_loaded = path.is_none();
+
+        Self {
+            name,
+            limit,
+            path,
+            state: Arc::new(Mutex::new(State {
+                tokens: limit.burst as f64,
+                refilled_at: Instant::now(),
+                quota: Quota::default(),
+                is_loaded,
+                unsaved: 0,
+            })),
+            saving: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    pub async fn acquire(&self, priority: Priority) -> Result<(), Error> {
+        self.load().await;
+
+        loop {
+            let acquired = {
+                let mut state = self.state.lock().expect("Lock rate limiter");
+                state.refill(self.limit);
+
+                if let Some(remaining) = self.remaining_in(&state) {
+                    let is_reserved = priority == Priority::Background && self.is_low(remaining);
+
+                    if remaining == 0 || is_reserved {
+                        return Err(Error::QuotaExhausted(self.name));
+                    }
+                }
+
+                if state.tokens >= 1.0 {
+                    state.tokens -= 1.0;
+                    state.quota.used += 1;
+                    state.unsaved += 1;
+
+                    let is_due = state.unsaved >= Self::SAVE_EVERY;
+
+                    if is_due {
+                        state.unsaved = 0;
+                    }
+
+                    Ok(is_due)
+                } else {
+                    Err(Duration::from_secs_f64(
+                        (1.0 - state.tokens) / self.limit.per_second,
+                    ))
+                }
+            };
+
+            match acquired {
+                Ok(is_due) => {
+                    if is_due {
+                        self.save().await;
+                    }
+
+                    return Ok(());
+                }
+                Err(wait) => {
+                    log::debug!("Rate limited by {} for {wait:?}", self.name);
+                    time::sleep(wait).await;
+                }
+            }
+        }
+    }
+
+    pub async fn remaining(&self) -> Option<u32> {
+        self.load().await;
+
+        let mut state = self.state.lock().expect("Lock rate limiter");
+        state.refill(self.limit);
+
+        self.remaining_in(&state)
+    }
+
+    pub async fn is_budget_low(&self) -> bool {
+        self.remaining()
+            .await
+            .is_some_and(|remaining| self.is_low(remaining))
+    }
+
+    fn remaining_in(&self, state: &State) -> Option<u32> {
+        let daily = self.limit.daily?;
+
+        Some(daily.saturating_sub(state.quota.used))
+    }
+
+    fn is_low(&self, remaining: u32) -> bool {
+        self.limit
+            .daily
+            .is_some_and(|daily| (remaining as f64) < daily as f64 * Self::RESERVE)
+    }
+
+    // The quota is read on the first request, so creating a session never blocks
+    async fn load(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        if self.state.lock().expect("Lock rate limiter").is_loaded {
+            return;
+        }
+
+        let quota = fs::read_to_string(path)
+            .await
+            .ok()
+            .and_then(|quota| ron::from_str(&quota).ok());
+
+        let mut state = self.state.lock().expect("Lock rate limiter");
+
+        if !state.is_loaded {
+            state.is_loaded = true;
+
+            if let Some(quota) = quota {
+                state.quota = quota;
+            }
+        }
+    }
+
+    // Saves are written one at a time and renamed into place, so a torn
+    // write can never reset the quota. The latest quota is saved.
+    async fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let _saving = self.saving.lock().await;
+        let quota = self.state.lock().expect("Lock rate limiter").quota;
+
+        let Ok(quota) = ron::to_string(&quota) else {
+            return;
+        };
+
+        let temporary = path.with_extension("ron.tmp");
+        let _ = fs::create_dir_all(path.parent().unwrap_or(path)).await;
+
+        let result = async {
+            fs::write(&temporary, quota).await?;
+            fs::rename(&temporary, path).await
+        };
+
+        if let Err(error) = result.await {
+            log::warn!("Failed to save quota of {}: {error}", self.name);
+        }
+    }
+}
+
+impl State {
+    fn refill(&mut self, limit: Limit) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+
+        self.tokens = (self.tokens + elapsed * limit.per_second).min(limit.burst as f64);
+        self.refilled_at = now;
+
+        let today = today();
+
+        if self.quota.day != today {
+            self.quota = Quota {
+                day: today,
+                used: 0,
+            };
+        }
+    }
+}
+
+fn today() -> u64 {
+    const DAY: u64 = 60 * 60 * 24;
+
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / DAY
+}
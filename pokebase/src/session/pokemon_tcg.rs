@@ -1,17 +1,42 @@
-use crate::session;
-use crate::{Card, Error};
+use crate::card;
+use crate::session::limiter::{self, Limiter, Priority};
+use crate::session::{self, Conditional, Config, ImageProvider, Validator};
+use crate::set;
+use crate::{Card, Database, Error};
 
 use bytes::Bytes;
+use futures_util::future::BoxFuture;
 use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Clone)]
 pub struct PokemonTcg {
     client: reqwest::Client,
     api_key: Option<String>,
+    api_url: String,
+    images_url: String,
+    limiter: Limiter,
+    priority: Priority,
 }
 
 impl PokemonTcg {
-    pub fn new(api_key: Option<String>) -> Self {
+    pub const NAME: &str = "PokemonTCG";
+
+    const LIMIT: limiter::Limit = limiter::Limit {
+        burst: 10,
+        per_second: 5.0,
+        daily: Some(20_000),
+    };
+
+    const LIMIT_WITHOUT_API_KEY: limiter::Limit = limiter::Limit {
+        burst: 5,
+        per_second: 0.5,
+        daily: Some(1_000),
+    };
+
+    pub fn new(config: &Config) -> Self {
+        let api_key = config.pokemon_tcg_api_key.clone();
+
         if let Some(api_key) = api_key.as_ref() {
             log::info!(
                 "PokemonTCG session started (API key: {})",
@@ -23,26 +48,77 @@ impl PokemonTcg {
 
         Self {
             client: session::CLIENT.clone(),
+            limiter: Limiter::new(
+                Self::NAME,
+                if api_key.is_some() {
+                    Self::LIMIT
+                } else {
+                    Self::LIMIT_WITHOUT_API_KEY
+                },
+                config.quotas.as_deref(),
+            ),
+            priority: Priority::Foreground,
             api_key,
+            api_url: config.endpoints.pokemon_tcg_api.clone(),
+            images_url: config.endpoints.pokemon_tcg_images.clone(),
         }
     }
 
-    pub async fn download_image(&self, card: &Card) -> Result<Bytes, Error> {
-        if !card.name.has_english() {
+    pub fn background(&self) -> Self {
+        Self {
+            priority: Priority::Background,
+            ..self.clone()
+        }
+    }
+
+    pub fn limiter(&self) -> &Limiter {
+        &self.limiter
+    }
+
+    pub async fn download_image(
+        &self,
+        card: &Card,
+        resolution: Resolution,
+        locale: Option<&str>,
+        validator: &Validator,
+    ) -> Result<Conditional<Bytes>, Error> {
+        let is_foreign =
+            locale.is_some_and(|locale| locale != "en" && card.name.get(locale).is_some());
+
+        if !card.name.has_english() || is_foreign {
             return Err(Error::LocaleNotAvailable);
         }
 
         let set = set_name(card);
         let number = card_number(card);
-        let url = format!("https://images.pokemontcg.io/{set}/{number}_hires.png");
+        let url = format!(
+            "{images_url}/{set}/{number}{suffix}.png",
+            images_url = self.images_url,
+            suffix = match resolution {
+                Resolution::Low => "",
+                Resolution::High => "_hires",
+            }
+        );
 
         log::info!("Downloading image: {url}");
-        let response = session::retry(2, || self.get(&url).send()).await?;
+        let response = session::retry(2, async || {
+            self.limiter.acquire(self.priority).await?;
+
+            Ok(validator.apply(Self::NAME, self.get(&url)).send().await?)
+        })
+        .await?;
 
-        Ok(response.error_for_status()?.bytes().await?)
+        Conditional::from_response(Self::NAME, response, async |response| {
+            Ok(response.bytes().await?)
+        })
+        .await
     }
 
-    pub async fn fetch_pricing(&self, card: &Card) -> Result<Pricing, Error> {
+    pub async fn fetch_pricing(
+        &self,
+        card: &Card,
+        validator: &Validator,
+    ) -> Result<Conditional<Pricing>, Error> {
         if !card.name.has_english() {
             return Err(Error::LocaleNotAvailable);
         }
@@ -50,7 +126,7 @@ impl PokemonTcg {
         let number = card_number(card);
         let set = set_name(card);
 
-        let url = format!("https://api.pokemontcg.io/v2/cards/{set}-{number}");
+        let url = format!("{api_url}/cards/{set}-{number}", api_url = self.api_url);
 
         #[derive(Deserialize)]
         struct Response {
@@ -59,11 +135,105 @@ impl PokemonTcg {
 
         log::info!("Fetching price: {url}");
 
-        let response =
-            session::retry(2, async || self.get(&url).send().await?.error_for_status()).await?;
-        let response: Response = response.json().await?;
+        let response = session::retry(2, async || {
+            self.limiter.acquire(self.priority).await?;
+
+            Ok(validator.apply(Self::NAME, self.get(&url)).send().await?)
+        })
+        .await?;
 
-        Ok(response.data)
+        Conditional::from_response(Self::NAME, response, async |response| {
+            let response: Response = response.json().await?;
+
+            Ok(response.data)
+        })
+        .await
+    }
+
+    pub async fn fetch_set_pricing(
+        &self,
+        set: &set::Id,
+        database: &Database,
+    ) -> Result<Vec<(card::Id, Pricing)>, Error> {
+        // PokemonTCG splits some of our sets (e.g. trainer galleries)
+        let mut sets: BTreeMap<String, Vec<&Card>> = BTreeMap::new();
+
+        for card in database.cards.values() {
+            if card.set == *set && card.name.has_english() {
+                sets.entry(set_name(card)).or_default().push(card);
+            }
+        }
+
+        let mut prices = Vec::new();
+
+        for (set, cards) in sets {
+            let mut pricing = self.fetch_pages(&set).await?;
+
+            prices.extend(cards.into_iter().filter_map(|card| {
+                let pricing = pricing.remove(&format!("{set}-{}", card_number(card)))?;
+
+                Some((card.id.clone(), pricing))
+            }));
+        }
+
+        Ok(prices)
+    }
+
+    async fn fetch_pages(&self, set: &str) -> Result<HashMap<String, Pricing>, Error> {
+        const PAGE_SIZE: usize = 250;
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Response {
+            data: Vec<Entry>,
+            total_count: usize,
+        }
+
+        #[derive(Deserialize)]
+        struct Entry {
+            id: String,
+            #[serde(flatten)]
+            pricing: Pricing,
+        }
+
+        let mut prices = HashMap::new();
+        let mut page = 1;
+
+        loop {
+            let url = format!(
+                "{api_url}/cards?q=set.id:{set}&page={page}&pageSize={PAGE_SIZE}\
+                &select=id,tcgplayer,cardmarket",
+                api_url = self.api_url
+            );
+
+            log::info!("Fetching prices: {url}");
+
+            let response = session::retry(2, async || {
+                self.limiter.acquire(self.priority).await?;
+
+                Ok(self.get(&url).send().await?)
+            })
+            .await?;
+            let response: Response = response.json().await?;
+
+            let is_last =
+                response.data.len() < PAGE_SIZE || page * PAGE_SIZE >= response.total_count;
+
+            prices.extend(
+                response
+                    .data
+                    .into_iter()
+                    .map(|entry| (entry.id, entry.pricing)),
+            );
+
+            if is_last {
+                break;
+            }
+
+            page += 1;
+        }
+
+        Ok(prices)
     }
 
     fn get(&self, url: impl AsRef<str>) -> reqwest::RequestBuilder {
@@ -77,6 +247,25 @@ impl PokemonTcg {
     }
 }
 
+impl ImageProvider for PokemonTcg {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn download_image<'a>(
+        &'a self,
+        card: &'a Card,
+        _database: &'a Database,
+        resolution: Resolution,
+        locale: Option<&'a str>,
+        validator: &'a Validator,
+    ) -> BoxFuture<'a, Result<Conditional<Bytes>, Error>> {
+        Box::pin(PokemonTcg::download_image(
+            self, card, resolution, locale, validator,
+        ))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
 pub struct Pricing {
     #[serde(default)]
@@ -117,12 +306,7 @@ fn set_name(card: &Card) -> String {
 }
 
 fn card_number(card: &Card) -> &str {
-    card.id
-        .as_str()
-        .rsplit("-")
-        .next()
-        .unwrap_or(card.id.as_str())
-        .trim_start_matches('0')
+    card.id.number().trim_start_matches('0')
 }
 
 pub mod tcgplayer {
@@ -1,58 +1,285 @@
-use crate::session;
+use crate::card::Resolution;
+use crate::session::limiter::{self, Limiter, Priority};
+use crate::session::pokemon_tcg::{self, cardmarket, tcgplayer};
+use crate::session::{self, Conditional, Config, ImageProvider, Validator};
 use crate::{Card, Database, Error, Locale};
 
 use bytes::Bytes;
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
 
 #[derive(Debug, Clone)]
 pub struct Tcgdex {
     client: reqwest::Client,
+    api_url: String,
+    assets_url: String,
+    limiter: Limiter,
+    priority: Priority,
 }
 
 impl Tcgdex {
-    pub fn new() -> Self {
+    pub const NAME: &str = "TCGdex";
+
+    // TCGdex does not document any limits, so let's be nice
+    const LIMIT: limiter::Limit = limiter::Limit {
+        burst: 10,
+        per_second: 5.0,
+        daily: None,
+    };
+
+    pub fn new(config: &Config) -> Self {
         Self {
             client: session::CLIENT.clone(),
+            api_url: config.endpoints.tcgdex_api.clone(),
+            assets_url: config.endpoints.tcgdex_assets.clone(),
+            limiter: Limiter::new(Self::NAME, Self::LIMIT, config.quotas.as_deref()),
+            priority: Priority::Foreground,
+        }
+    }
+
+    pub fn background(&self) -> Self {
+        Self {
+            priority: Priority::Background,
+            ..self.clone()
         }
     }
 
-    pub async fn download_image(&self, card: &Card, database: &Database) -> Result<Bytes, Error> {
+    pub fn limiter(&self) -> &Limiter {
+        &self.limiter
+    }
+
+    pub async fn download_image(
+        &self,
+        card: &Card,
+        database: &Database,
+        resolution: Resolution,
+        locale: Option<&str>,
+        validator: &Validator,
+    ) -> Result<Conditional<Bytes>, Error> {
         let Some(set) = database.sets.get(&card.set) else {
             return Err(Error::SetNotFound(card.set.clone()));
         };
 
-        let locale = if card.name.has_english() {
-            "en" // TODO
-        } else if card.name.has_japanese() {
-            "ja"
-        } else {
-            card.name
-                .locales()
-                .next()
-                .map(Locale::as_str)
-                .unwrap_or("en")
-        };
+        // The requested locale is only honored if the card was printed in it
+        let locale = locale
+            .filter(|locale| card.name.get(*locale).is_some())
+            .unwrap_or_else(|| self::locale(card));
 
         let url = format!(
-            "https://assets.tcgdex.net/{locale}/{series}/{set}/{number}/high.png",
+            "{assets_url}/{locale}/{series}/{set}/{number}/{quality}.png",
+            assets_url = self.assets_url,
+            quality = match resolution {
+                Resolution::Low => "low",
+                Resolution::High => "high",
+            },
             series = set.series.as_str(),
             set = card.set.as_str(),
-            number = card
-                .id
-                .as_str()
-                .rsplit("-")
-                .next()
-                .unwrap_or(card.id.as_str())
+            number = card.id.number(),
         );
 
         log::info!("Downloading image: {url}");
-        let response = session::retry(2, || self.client.get(&url).send()).await;
+        let response = session::retry(2, async || {
+            self.limiter.acquire(self.priority).await?;
+
+            Ok(validator
+                .apply(Self::NAME, self.client.get(&url))
+                .send()
+                .await?)
+        })
+        .await?;
+
+        Conditional::from_response(Self::NAME, response, async |response| {
+            Ok(response.bytes().await?)
+        })
+        .await
+    }
+
+    // TCGdex prices are mapped into the PokemonTCG structures, so both
+    // providers are interchangeable
+    pub async fn fetch_pricing(
+        &self,
+        card: &Card,
+        validator: &Validator,
+    ) -> Result<Conditional<pokemon_tcg::Pricing>, Error> {
+        let url = format!(
+            "{api_url}/{locale}/cards/{id}",
+            api_url = self.api_url,
+            locale = locale(card),
+            id = card.id.as_str()
+        );
+
+        #[derive(Deserialize)]
+        struct Response {
+            #[serde(default)]
+            pricing: Option<Pricing>,
+        }
+
+        log::info!("Fetching price: {url}");
+
+        let response = session::retry(2, async || {
+            self.limiter.acquire(self.priority).await?;
+
+            Ok(validator
+                .apply(Self::NAME, self.client.get(&url))
+                .send()
+                .await?)
+        })
+        .await?;
+
+        Conditional::from_response(Self::NAME, response, async |response| {
+            let response: Response = response.json().await?;
+
+            // No pricing is not the same as free
+            Ok(response.pricing.ok_or(Error::NotAvailable)?.into())
+        })
+        .await
+    }
+}
+
+impl ImageProvider for Tcgdex {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn download_image<'a>(
+        &'a self,
+        card: &'a Card,
+        database: &'a Database,
+        resolution: Resolution,
+        locale: Option<&'a str>,
+        validator: &'a Validator,
+    ) -> BoxFuture<'a, Result<Conditional<Bytes>, Error>> {
+        Box::pin(Tcgdex::download_image(
+            self, card, database, resolution, locale, validator,
+        ))
+    }
+}
 
-        Ok(response?.error_for_status()?.bytes().await?)
+fn locale(card: &Card) -> &str {
+    if card.name.has_english() {
+        "en"
+    } else if card.name.has_japanese() {
+        "ja"
+    } else {
+        card.name
+            .locales()
+            .next()
+            .map(Locale::as_str)
+            .unwrap_or("en")
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Pricing {
+    #[serde(default)]
+    tcgplayer: Option<Tcgplayer>,
+    #[serde(default)]
+    cardmarket: Option<Cardmarket>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Tcgplayer {
+    #[serde(default)]
+    normal: Option<Spread>,
+    #[serde(default)]
+    holofoil: Option<Spread>,
+    #[serde(default)]
+    reverse_holofoil: Option<Spread>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Spread {
+    #[serde(default)]
+    low_price: Option<f64>,
+    #[serde(default)]
+    mid_price: Option<f64>,
+    #[serde(default)]
+    high_price: Option<f64>,
+    #[serde(default)]
+    market_price: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Cardmarket {
+    #[serde(default)]
+    avg: Option<f64>,
+    #[serde(default)]
+    low: Option<f64>,
+    #[serde(default)]
+    trend: Option<f64>,
+    #[serde(default)]
+    avg1: Option<f64>,
+    #[serde(default)]
+    avg7: Option<f64>,
+    #[serde(default)]
+    avg30: Option<f64>,
+    #[serde(default)]
+    avg_holo: Option<f64>,
+    #[serde(default)]
+    low_holo: Option<f64>,
+    #[serde(default)]
+    trend_holo: Option<f64>,
+    #[serde(default)]
+    avg1_holo: Option<f64>,
+    #[serde(default)]
+    avg7_holo: Option<f64>,
+    #[serde(default)]
+    avg30_holo: Option<f64>,
+}
+
+impl From<Pricing> for pokemon_tcg::Pricing {
+    fn from(pricing: Pricing) -> Self {
+        let spread = |spread: Spread| tcgplayer::Spread {
+            low: spread.low_price.unwrap_or_default(),
+            mid: spread.mid_price.unwrap_or_default(),
+            high: spread.high_price.unwrap_or_default(),
+            market: spread.market_price.unwrap_or_default(),
+        };
+
+        let tcgplayer = pricing
+            .tcgplayer
+            .map(|tcgplayer| tcgplayer::Pricing {
+                prices: tcgplayer::Prices {
+                    normal: tcgplayer.normal.map(spread),
+                    holofoil: tcgplayer.holofoil.map(spread),
+                    reverse_holofoil: tcgplayer.reverse_holofoil.map(spread),
+                },
+            })
+            .unwrap_or_default();
+
+        // TCGdex calls any foil variant "holo"; for most cards, it is the reverse holo
+        let cardmarket = pricing
+            .cardmarket
+            .map(|cardmarket| cardmarket::Pricing {
+                prices: cardmarket::Prices {
+                    average_sell_price: cardmarket.avg.unwrap_or_default(),
+                    low_price: cardmarket.low.unwrap_or_default(),
+                    trend_price: cardmarket.trend.unwrap_or_default(),
+                    avg1: cardmarket.avg1.unwrap_or_default(),
+                    avg7: cardmarket.avg7.unwrap_or_default(),
+                    avg30: cardmarket.avg30.unwrap_or_default(),
+                    reverse_holo_sell: cardmarket.avg_holo.unwrap_or_default(),
+                    reverse_holo_low: cardmarket.low_holo.unwrap_or_default(),
+                    reverse_holo_trend: cardmarket.trend_holo.unwrap_or_default(),
+                    reverse_holo_avg1: cardmarket.avg1_holo.unwrap_or_default(),
+                    reverse_holo_avg7: cardmarket.avg7_holo.unwrap_or_default(),
+                    reverse_holo_avg30: cardmarket.avg30_holo.unwrap_or_default(),
+                },
+            })
+            .unwrap_or_default();
+
+        Self {
+            tcgplayer,
+            cardmarket,
+        }
     }
 }
 
 impl Default for Tcgdex {
     fn default() -> Self {
-        Self::new()
+        Self::new(&Config::default())
     }
 }
@@ -0,0 +1,37 @@
+use pokebase::kana;
+
+#[test]
+fn hiragana_is_romanized() {
+    assert_eq!(kana::to_romaji("ぴかちゅう"), "pikachuu");
+    assert_eq!(kana::to_romaji("ふしぎだね"), "fushigidane");
+    assert_eq!(kana::to_romaji("しゃわーず"), "shawaazu");
+}
+
+#[test]
+fn katakana_is_romanized() {
+    assert_eq!(kana::to_romaji("ピカチュウ"), "pikachuu");
+    assert_eq!(kana::to_romaji("ハネッコ"), "hanekko");
+    assert_eq!(kana::to_romaji("ジョウト"), "jouto");
+}
+
+#[test]
+fn long_vowels_are_extended() {
+    assert_eq!(kana::to_romaji("リザードン"), "rizaadon");
+    assert_eq!(kana::to_romaji("ルギアー"), "rugiaa");
+    assert_eq!(kana::to_romaji("ーア"), "a");
+}
+
+#[test]
+fn small_tsu_doubles_the_next_consonant() {
+    assert_eq!(kana::to_romaji("ゼニガメっ"), "zenigame");
+    assert_eq!(kana::to_romaji("ポッチャマ"), "potchama");
+    assert_eq!(kana::to_romaji("ニャッス"), "nyassu");
+}
+
+#[test]
+fn long_vowels_are_ignored_when_searching() {
+    assert_eq!(kana::romanize("ピカチュウ"), "pikachu");
+    assert_eq!(kana::romanize("リザードン"), "rizadon");
+    assert_eq!(kana::romanize("ジョウト"), "joto");
+    assert_eq!(kana::romanize("pikachuu"), "pikachu");
+}
@@ -0,0 +1,486 @@
+mod stand_in;
+
+use pokebase::card::{self, pricing};
+use pokebase::{Card, Database, Error, Pokemon, Set, pokemon, session};
+
+use stand_in::StandIn;
+
+use bytes::Bytes;
+use futures_util::future::BoxFuture;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+#[tokio::test]
+async fn image_is_downloaded_from_pokemon_tcg() {
+    let stand_in = StandIn::start().await;
+    let session = stand_in.session();
+    let database = database();
+
+    let card = database.cards.get(&celebi().id).expect("Celebi V exists");
+    let image = card::Image::download(card, &database, &session, card::Resolution::High, None)
+        .await
+        .expect("Download image");
+
+    assert!(image.bytes.starts_with(b"\x89PNG"));
+}
+
+#[tokio::test]
+async fn image_is_downloaded_in_low_resolution() {
+    let stand_in = StandIn::start().await;
+    let mut session = stand_in.session();
+    let database = database();
+
+    session
+        .image_providers
+        .retain(|provider| provider.name() == "PokemonTCG");
+
+    let card = database.cards.get(&celebi().id).expect("Celebi V exists");
+    let image = card::Image::download(card, &database, &session, card::Resolution::Low, None)
+        .await
+        .expect("Download image");
+
+    assert_eq!(image.resolution, card::Resolution::Low);
+    assert!(image.bytes.starts_with(b"\x89PNG"));
+}
+
+#[tokio::test]
+async fn image_falls_back_to_tcgdex() {
+    let stand_in = StandIn::start().await;
+    let session = stand_in.session();
+    let database = database();
+
+    let card = database.cards.get(&hoppip().id).expect("Hoppip exists");
+    let image = card::Image::download(card, &database, &session, card::Resolution::High, None)
+        .await
+        .expect("Download image");
+
+    assert!(image.bytes.starts_with(b"\x89PNG"));
+}
+
+#[tokio::test]
+async fn image_is_downloaded_in_requested_locale() {
+    let stand_in = StandIn::start().await;
+    let session = stand_in.session();
+    let database = database();
+
+    let card = database.cards.get(&celebi().id).expect("Celebi V exists");
+    let image = card::Image::download(
+        card,
+        &database,
+        &session,
+        card::Resolution::High,
+        Some("fr"),
+    )
+    .await
+    .expect("Download image");
+
+    assert_eq!(image.provider, "TCGdex");
+    assert!(image.bytes.starts_with(b"\x89PNG"));
+}
+
+#[tokio::test]
+async fn image_download_fails_when_missing() {
+    let stand_in = StandIn::start().await;
+    let mut session = stand_in.session();
+    let database = database();
+
+    session
+        .image_providers
+        .retain(|provider| provider.name() != "TCGdex");
+
+    let card = database.cards.get(&hoppip().id).expect("Hoppip exists");
+
+    assert!(matches!(
+        card::Image::download(card, &database, &session, card::Resolution::High, None).await,
+        Err(Error::NotAvailable)
+    ));
+}
+
+#[tokio::test]
+async fn image_is_not_missing_when_a_provider_fails() {
+    let stand_in = StandIn::start().await;
+    let mut session = stand_in.session();
+    let database = database();
+
+    // Accepts connections, but never answers
+    let silent = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Bind silent server");
+
+    session.image_providers = vec![
+        Arc::new(Failing {
+            url: format!(
+                "http://{}/image.png",
+                silent.local_addr().expect("Get address")
+            ),
+        }),
+        Arc::new(Failing {
+            url: format!("{}/missing.png", stand_in.endpoints().tcgdex_assets),
+        }),
+    ];
+
+    let card = database.cards.get(&celebi().id).expect("Celebi V exists");
+    let error = card::Image::download(card, &database, &session, card::Resolution::High, None)
+        .await
+        .expect_err("Download must fail");
+
+    // Only `NotAvailable` marks an image as missing
+    assert!(!matches!(error, Error::NotAvailable), "{error}");
+}
+
+#[tokio::test]
+async fn image_is_revalidated() {
+    let stand_in = StandIn::start().await;
+    let session = stand_in.session();
+    let database = database();
+
+    let card = database.cards.get(&celebi().id).expect("Celebi V exists");
+    let image = card::Image::download(card, &database, &session, card::Resolution::High, None)
+        .await
+        .expect("Download image");
+
+    assert!(image.validator.etag.is_some());
+
+    let revalidated = card::Image::revalidate(
+        card,
+        &database,
+        &session,
+        image.resolution,
+        None,
+        image.provider,
+        &image.validator,
+    )
+    .await
+    .expect("Revalidate image");
+
+    assert!(revalidated.is_none());
+
+    let outdated = session::Validator {
+        provider: Some(image.provider.to_owned()),
+        etag: Some("\"outdated\"".to_owned()),
+        last_modified: None,
+    };
+
+    let revalidated = card::Image::revalidate(
+        card,
+        &database,
+        &session,
+        image.resolution,
+        None,
+        image.provider,
+        &outdated,
+    )
+    .await
+    .expect("Revalidate image");
+
+    assert!(revalidated.is_some());
+}
+
+#[tokio::test]
+async fn pricing_is_fetched() {
+    let stand_in = StandIn::start().await;
+    let session = stand_in.session();
+
+    let pricing = pricing::Pricing::fetch(&celebi(), &session)
+        .await
+        .expect("Fetch pricing");
+
+    let holofoil = pricing.tcgplayer.prices.holofoil.expect("Holofoil pricing");
+
+    assert_eq!(holofoil.mid, 2.25);
+    assert_eq!(pricing.cardmarket.prices.trend_price, 1.9);
+    assert_eq!(pricing.tcgplayer.prices.normal, None);
+
+    let revalidated = pricing::Pricing::revalidate(&celebi(), &session, &pricing.validator)
+        .await
+        .expect("Revalidate pricing");
+
+    assert!(revalidated.is_none());
+}
+
+#[tokio::test]
+async fn pricing_falls_back_to_tcgdex() {
+    let stand_in = StandIn::start().await;
+    let session = stand_in.session();
+
+    let pricing = pricing::Pricing::fetch(&hoppip(), &session)
+        .await
+        .expect("Fetch pricing");
+
+    assert_eq!(pricing.cardmarket.prices.trend_price, 0.15);
+    assert_eq!(pricing.cardmarket.prices.reverse_holo_trend, 0.55);
+    assert_eq!(pricing.tcgplayer.prices.normal, None);
+}
+
+#[tokio::test]
+async fn pricing_is_not_available_without_prices() {
+    let stand_in = StandIn::start().await;
+    let session = stand_in.session();
+
+    let skiploom = Card {
+        id: ron::from_str(r#"("SV1a-002")"#).expect("Deserialize id"),
+        ..hoppip()
+    };
+
+    assert!(matches!(
+        pricing::Pricing::fetch(&skiploom, &session).await,
+        Err(Error::NotAvailable)
+    ));
+}
+
+#[tokio::test]
+async fn validator_is_only_sent_to_its_provider() {
+    let stand_in = StandIn::start().await;
+    let session = stand_in.session();
+
+    let pricing = pricing::Pricing::fetch(&hoppip(), &session)
+        .await
+        .expect("Fetch pricing");
+
+    assert_eq!(pricing.validator.provider.as_deref(), Some("TCGdex"));
+
+    let revalidated = pricing::Pricing::revalidate(&hoppip(), &session, &pricing.validator)
+        .await
+        .expect("Revalidate pricing");
+
+    assert!(revalidated.is_none());
+
+    let foreign = session::Validator {
+        provider: Some("PokemonTCG".to_owned()),
+        ..pricing.validator
+    };
+
+    let revalidated = pricing::Pricing::revalidate(&hoppip(), &session, &foreign)
+        .await
+        .expect("Revalidate pricing");
+
+    assert!(revalidated.is_some());
+}
+
+#[tokio::test]
+async fn set_pricing_is_fetched() {
+    let stand_in = StandIn::start().await;
+    let session = stand_in.session();
+    let database = database();
+
+    let prices = pricing::Pricing::fetch_set(&celebi().set, &database, &session)
+        .await
+        .expect("Fetch set pricing");
+
+    assert_eq!(prices.len(), 1);
+
+    let (card, pricing) = &prices[0];
+    let holofoil = pricing.tcgplayer.prices.holofoil.expect("Holofoil pricing");
+
+    assert_eq!(card, &celebi().id);
+    assert_eq!(holofoil.mid, 2.25);
+}
+
+#[tokio::test]
+async fn exchange_rate_is_fetched() {
+    let stand_in = StandIn::start().await;
+    let session = stand_in.session();
+
+    let rate = session
+        .frankfurter
+        .fetch_rate("USD", "EUR")
+        .await
+        .expect("Fetch exchange rate");
+
+    assert_eq!(rate, 0.88);
+    assert!(session.frankfurter.fetch_rate("USD", "JPY").await.is_err());
+}
+
+#[tokio::test]
+async fn quota_is_loaded_from_disk() {
+    use std::time::SystemTime;
+
+    let quotas = std::env::temp_dir().join(format!("pokebase-quotas-{}", std::process::id()));
+    let today = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("Get time")
+        .as_secs()
+        / (60 * 60 * 24);
+
+    tokio::fs::create_dir_all(&quotas)
+        .await
+        .expect("Create quotas directory");
+    tokio::fs::write(quotas.join("test.ron"), format!("(day: {today}, used: 10)"))
+        .await
+        .expect("Write quota");
+
+    let limiter = session::Limiter::new(
+        "Test",
+        session::limiter::Limit {
+            burst: 1,
+            per_second: 1.0,
+            daily: Some(10),
+        },
+        Some(&quotas),
+    );
+
+    assert_eq!(limiter.remaining().await, Some(0));
+    assert!(matches!(
+        limiter.acquire(session::Priority::Foreground).await,
+        Err(Error::QuotaExhausted("Test"))
+    ));
+
+    let _ = tokio::fs::remove_dir_all(&quotas).await;
+}
+
+#[tokio::test]
+async fn background_session_keeps_image_providers() {
+    let stand_in = StandIn::start().await;
+    let mut session = stand_in.session();
+
+    session
+        .image_providers
+        .retain(|provider| provider.name() == "TCGdex");
+
+    let background = session.background();
+    let names: Vec<_> = background
+        .image_providers
+        .iter()
+        .map(|provider| provider.name())
+        .collect();
+
+    assert_eq!(names, ["TCGdex"]);
+    assert!(!Arc::ptr_eq(
+        &session.image_providers[0],
+        &background.image_providers[0]
+    ));
+}
+
+#[tokio::test]
+async fn card_is_found_by_romaji() {
+    let database = database();
+
+    for query in ["hanekko", "はねっこ", "ハネッコ"] {
+        let search = card::search(query, &database).await;
+        let ids: Vec<_> = search
+            .matches()
+            .iter()
+            .map(|card| card.id.clone())
+            .collect();
+
+        assert_eq!(ids, [hoppip().id], "{query}");
+    }
+
+    let search = card::search("celebi", &database).await;
+    let ids: Vec<_> = search
+        .matches()
+        .iter()
+        .map(|card| card.id.clone())
+        .collect();
+
+    assert_eq!(ids, [celebi().id]);
+
+    // Cards are found by the Pokémon they show, too
+    let search = card::search("hoppip", &database).await;
+    let ids: Vec<_> = search
+        .matches()
+        .iter()
+        .map(|card| card.id.clone())
+        .collect();
+
+    assert_eq!(ids, [hoppip().id]);
+}
+
+// An image provider that fetches a single URL, which is expected to fail
+#[derive(Debug)]
+struct Failing {
+    url: String,
+}
+
+impl session::ImageProvider for Failing {
+    fn name(&self) -> &'static str {
+        "Failing"
+    }
+
+    fn download_image<'a>(
+        &'a self,
+        _card: &'a Card,
+        _database: &'a Database,
+        _resolution: card::Resolution,
+        _locale: Option<&'a str>,
+        _validator: &'a session::Validator,
+    ) -> BoxFuture<'a, Result<session::Conditional<Bytes>, Error>> {
+        Box::pin(async move {
+            let client = reqwest::Client::builder()
+                .timeout(Duration::from_millis(100))
+                .build()?;
+
+            let response = client.get(&self.url).send().await?.error_for_status()?;
+
+            Ok(session::Conditional::Modified(
+                response.bytes().await?,
+                session::Validator::default(),
+            ))
+        })
+    }
+}
+
+fn database() -> Database {
+    let sets: Vec<Set> = vec![
+        ron::from_str(
+            r#"(
+                id: ("swsh1"),
+                name: {"en": "Sword & Shield"},
+                series: ("swsh"),
+                release_date: "2020-02-07",
+                total_cards: 216,
+            )"#,
+        )
+        .expect("Deserialize set"),
+        ron::from_str(
+            r#"(
+                id: ("SV1a"),
+                name: {"ja": "トリプレットビート"},
+                series: ("SV"),
+                release_date: "2023-03-10",
+                total_cards: 73,
+            )"#,
+        )
+        .expect("Deserialize set"),
+    ];
+
+    let pokemon = vec![Pokemon {
+        id: ron::from_str::<pokemon::Id>("187").expect("Deserialize Pokédex number"),
+        name: ron::from_str(r#"{"en": "Hoppip"}"#).expect("Deserialize name"),
+    }];
+
+    Database::new(pokemon, Vec::new(), sets, vec![celebi(), hoppip()])
+}
+
+fn celebi() -> Card {
+    ron::from_str(
+        r#"(
+            id: ("swsh1-1"),
+            set: ("swsh1"),
+            name: {"en": "Celebi V", "fr": "Celebi-V"},
+            types: [Grass],
+            rarity: HoloRareV,
+            variants: (first_edition: false, holo: true, normal: false, reverse: false, w_promo: false),
+            illustrator: Some("PLANETA Mochizuki"),
+            pokedex: [251],
+        )"#,
+    )
+    .expect("Deserialize card")
+}
+
+fn hoppip() -> Card {
+    ron::from_str(
+        r#"(
+            id: ("SV1a-001"),
+            set: ("SV1a"),
+            name: {"ja": "ハネッコ"},
+            types: [Grass],
+            rarity: Common,
+            variants: (first_edition: false, holo: false, normal: true, reverse: true, w_promo: false),
+            illustrator: None,
+            pokedex: [187],
+        )"#,
+    )
+    .expect("Deserialize card")
+}
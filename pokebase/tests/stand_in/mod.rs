@@ -0,0 +1,133 @@
+use pokebase::session::{self, Endpoints, Session};
+
+use std::io;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use tokio::fs;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+// A tiny HTTP server that serves the files in `tests/fixtures`, so the
+// networking of a `Session` can be tested offline.
+pub struct StandIn {
+    address: SocketAddr,
+}
+
+impl StandIn {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Bind stand-in server");
+
+        let address = listener.local_addr().expect("Get stand-in address");
+
+        let _ = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let _ = tokio::spawn(serve(stream));
+            }
+        });
+
+        Self { address }
+    }
+
+    pub fn endpoints(&self) -> Endpoints {
+        let base = format!("http://{}", self.address);
+
+        Endpoints {
+            pokemon_tcg_api: format!("{base}/pokemontcg/v2"),
+            pokemon_tcg_images: format!("{base}/pokemontcg/images"),
+            tcgdex_api: format!("{base}/tcgdex/api"),
+            tcgdex_assets: format!("{base}/tcgdex/assets"),
+            exchange_rates: format!("{base}/frankfurter"),
+        }
+    }
+
+    pub fn session(&self) -> Session {
+        Session::with_config(session::Config {
+            endpoints: self.endpoints(),
+            ..session::Config::default()
+        })
+    }
+}
+
+async fn serve(mut stream: TcpStream) -> io::Result<()> {
+    let (reader, mut writer) = stream.split();
+    let mut reader = BufReader::new(reader);
+
+    let mut request = String::new();
+    let _ = reader.read_line(&mut request).await?;
+
+    let mut if_none_match = None;
+
+    loop {
+        let mut header = String::new();
+        let _ = reader.read_line(&mut header).await?;
+
+        if header.trim().is_empty() {
+            break;
+        }
+
+        if let Some((_, value)) = header
+            .split_once(':')
+            .filter(|(name, _)| name.eq_ignore_ascii_case("if-none-match"))
+        {
+            if_none_match = Some(value.trim().to_owned());
+        }
+    }
+
+    let path = request.split_whitespace().nth(1).unwrap_or("/");
+    let path = path.split('?').next().unwrap_or(path);
+
+    let fixture = fixtures().join(path.trim_start_matches('/'));
+
+    let body = if path.contains("..") {
+        None
+    } else if let Ok(body) = fs::read(&fixture).await {
+        Some(body)
+    } else {
+        fs::read(fixture.with_extension("json")).await.ok()
+    };
+
+    let etag = body.as_deref().map(etag);
+
+    let (status, body) = match body {
+        Some(_) if if_none_match.is_some() && if_none_match == etag => {
+            ("304 Not Modified", Vec::new())
+        }
+        Some(body) => ("200 OK", body),
+        None => ("404 Not Found", Vec::new()),
+    };
+
+    let etag = etag
+        .map(|etag| format!("ETag: {etag}\r\n"))
+        .unwrap_or_default();
+
+    writer
+        .write_all(
+            format!(
+                "HTTP/1.1 {status}\r\n\
+                Content-Length: {length}\r\n\
+                {etag}\
+                Connection: close\r\n\r\n",
+                length = body.len()
+            )
+            .as_bytes(),
+        )
+        .await?;
+
+    writer.write_all(&body).await?;
+    writer.shutdown().await
+}
+
+fn etag(body: &[u8]) -> String {
+    use std::hash::{DefaultHasher, Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+
+    format!("\"{:x}\"", hasher.finish())
+}
+
+fn fixtures() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
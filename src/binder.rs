@@ -176,14 +176,20 @@ impl Default for Set {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mode {
     GottaCatchEmAll,
+    // Every surplus copy, to bring to a trade
+    Trade,
+    // The cards we added last first
+    Recent,
 }
 
 impl Mode {
-    pub const ALL: &[Self] = &[Self::GottaCatchEmAll];
+    pub const ALL: &[Self] = &[Self::GottaCatchEmAll, Self::Trade, Self::Recent];
 
-    pub fn total_cards(self, database: &Database) -> usize {
+    pub fn total_cards(self, collection: &Collection, database: &Database) -> usize {
         match self {
             Self::GottaCatchEmAll => database.pokemon.len(),
+            Self::Trade => collection.surplus(database).len(),
+            Self::Recent => collection.recently_added().len(),
         }
     }
 
@@ -192,6 +198,7 @@ impl Mode {
             Self::GottaCatchEmAll => {
                 collection.total_pokemon(database) as f32 / database.pokemon.len() as f32 * 100.0
             }
+            Self::Trade | Self::Recent => 100.0,
         }
     }
 
@@ -207,6 +214,38 @@ impl Mode {
 
                 collection.rarest_card_for(pokemon, database)
             }
+            Mode::Trade => {
+                let surplus = collection.surplus(database);
+                let (card, _amount) = surplus.get(index)?;
+
+                database.cards.get(card)
+            }
+            Mode::Recent => {
+                let recent = collection.recently_added();
+
+                database.cards.get(recent.get(index)?)
+            }
+        }
+    }
+
+    // A wanted card that would fill the empty slot at the given index
+    pub fn wanted<'a>(
+        self,
+        index: usize,
+        collection: &Collection,
+        database: &'a Database,
+    ) -> Option<&'a Card> {
+        match self {
+            Mode::GottaCatchEmAll => {
+                let pokemon = database.pokemon.values().get(index)?;
+
+                collection
+                    .wishlist
+                    .keys()
+                    .filter_map(|card| database.cards.get(card))
+                    .find(|card| card.pokedex.first() == Some(&pokemon.id))
+            }
+            Mode::Trade | Mode::Recent => None,
         }
     }
 
@@ -220,6 +259,7 @@ impl Mode {
                     .copied()
                     .map(|pokemon| pokemon.number() - 1)
             }
+            Mode::Trade | Mode::Recent => None,
         }
     }
 }
@@ -228,6 +268,8 @@ impl fmt::Display for Mode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str(match self {
             Mode::GottaCatchEmAll => "Gotta Catch 'Em All",
+            Mode::Trade => "Trade Binder",
+            Mode::Recent => "Recently Added",
         })
     }
 }
@@ -1,22 +1,34 @@
+pub mod cache;
+pub mod language;
 pub mod pricing;
+pub mod queue;
+pub mod store;
 
-pub use crate::pokebase::card::{Card, Id, Search, search};
+pub use crate::pokebase::card::{Card, Id, Resolution, Search, search};
+pub use language::Language;
 pub use pricing::Pricing;
+pub use queue::Queue;
+pub use store::Store;
 
+use crate::pokebase;
 use crate::pokebase::card;
+use crate::pokebase::session::Validator;
 use crate::pokebase::{Database, Session};
 
 use bytes::Bytes;
-use futures_util::TryFutureExt;
-use std::env;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 use tokio::fs;
 use tokio::task;
 
 #[derive(Clone)]
 pub struct Image {
+    pub resolution: Resolution,
+    // The language the image was asked for, even if it was not printed in it
+    pub language: Option<Language>,
     pub width: u32,
     pub height: u32,
     pub rgba: Bytes,
@@ -30,61 +42,316 @@ impl Image {
         card: &Card,
         database: &Database,
         session: &Session,
-    ) -> impl Future<Output = Result<Image, anywho::Error>> + 'a {
+        resolution: Resolution,
+        language: Option<Language>,
+        priority: queue::Priority,
+    ) -> impl Future<Output = Result<Option<Image>, anywho::Error>> + 'a {
         let card = card.clone();
         let database = database.clone();
         let session = session.clone();
+        let localized = language.and_then(|language| language.for_card(&card));
+
+        async move {
+            // Our own photos always win over the official artwork
+            if let Ok(bytes) = fs::read(custom_path(&card.id)).await {
+                let image = decode(&card, resolution, Bytes::from(bytes)).await?;
+
+                return Ok(Some(Image { language, ..image }));
+            }
+
+            let mut image =
+                fetch_in(&card, &database, &session, resolution, localized, priority).await?;
+
+            // A card in the default language beats no card at all
+            if image.is_none() && localized.is_some() {
+                image = fetch_in(&card, &database, &session, resolution, None, priority).await?;
+            }
+
+            Ok(image.map(|image| Image { language, ..image }))
+        }
+    }
+
+    // Custom images are normalized to PNG and kept in the data directory,
+    // since they cannot be downloaded again.
+    pub async fn attach(card: card::Id, source: PathBuf) -> Result<(), anywho::Error> {
+        let png = normalize(&source).await?;
+        let path = custom_path(&card);
+
+        fs::create_dir_all(path.parent().unwrap_or(&path)).await?;
+        fs::write(&path, png).await?;
+
+        log::info!(
+            "Attached {source} to {id}",
+            source = source.display(),
+            id = card.as_str()
+        );
+
+        Ok(())
+    }
+
+    // Photos of a single copy are never overwritten, so undoing an edit can
+    // bring them back; returns the photo, relative to the custom images.
+    pub async fn attach_copy(card: card::Id, source: PathBuf) -> Result<PathBuf, anywho::Error> {
+        let png = normalize(&source).await?;
+
+        let taken_at = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+
+        let photo =
+            PathBuf::from("copies").join(format!("{id}-{taken_at}.png", id = card.as_str()));
+        let path = custom_dir().join(&photo);
+
+        fs::create_dir_all(path.parent().unwrap_or(&path)).await?;
+        fs::write(&path, png).await?;
+
+        log::info!(
+            "Attached {source} to a copy of {id}",
+            source = source.display(),
+            id = card.as_str()
+        );
+
+        Ok(photo)
+    }
+
+    pub fn open<'a>(
+        card: &Card,
+        photo: &Path,
+    ) -> impl Future<Output = Result<Image, anywho::Error>> + 'a {
+        let card = card.clone();
+        let path = custom_dir().join(photo);
 
         async move {
-            let cache = cache_dir().join(format!("{id}.png", id = card.id.as_str()));
+            let bytes = fs::read(path).await?;
+
+            decode(&card, Resolution::High, Bytes::from(bytes)).await
+        }
+    }
+
+    pub fn is_attached(card: &card::Id) -> bool {
+        custom_path(card).exists()
+    }
+
+    // Returns `false` if the card had no custom image
+    pub async fn detach(card: card::Id) -> Result<bool, anywho::Error> {
+        match fs::remove_file(custom_path(&card)).await {
+            Ok(()) => Ok(true),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(error) => Err(error)?,
+        }
+    }
+
+    // Processed images are stored raw, prefixed by a small header:
+    // the magic bytes, followed by the width and height in little endian.
+    //
+    // Only low resolution images are kept raw; a high resolution one takes
+    // about 3 MB and is only decoded when a card is opened.
+    const MAGIC: &[u8; 4] = b"HDCK";
+    const HEADER: usize = 12;
+
+    async fn load(path: &Path, resolution: Resolution) -> Option<Self> {
+        if resolution != Resolution::Low {
+            return None;
+        }
+
+        let bytes = Bytes::from(fs::read(path).await.ok()?);
+
+        if bytes.len() < Self::HEADER || &bytes[..4] != Self::MAGIC {
+            return None;
+        }
+
+        let width = u32::from_le_bytes(bytes[4..8].try_into().ok()?);
+        let height = u32::from_le_bytes(bytes[8..12].try_into().ok()?);
+
+        // Truncated files are simply processed again
+        if bytes.len() - Self::HEADER != width as usize * height as usize * 4 {
+            return None;
+        }
+
+        Some(Self {
+            resolution,
+            language: None,
+            width,
+            height,
+            rgba: bytes.slice(Self::HEADER..),
+        })
+    }
+
+    async fn save(&self, path: &Path) {
+        if self.resolution != Resolution::Low {
+            return;
+        }
+
+        let mut bytes = Vec::with_capacity(Self::HEADER + self.rgba.len());
+        bytes.extend_from_slice(Self::MAGIC);
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes.extend_from_slice(&self.rgba);
+
+        let _ = fs::write(path, bytes).await;
+    }
+}
+
+// Fetches the image of a card in a specific language, from the cache if possible
+async fn fetch_in(
+    card: &Card,
+    database: &Database,
+    session: &Session,
+    resolution: Resolution,
+    language: Option<Language>,
+    priority: queue::Priority,
+) -> Result<Option<Image>, anywho::Error> {
+    let cache = cache_dir(resolution, language).join(format!("{id}.png", id = card.id.as_str()));
+    let metadata = cache.with_extension("ron");
+    let processed = cache.with_extension("rgba");
+    let missing = cache.with_extension("missing");
+
+    // Images no provider has are only asked for once in a while
+    if is_missing(&missing).await {
+        return Ok(None);
+    }
+
+    let download_image = async || {
+        let _permit = Queue::global().acquire(priority).await;
+
+        let image = match card::Image::download(
+            card,
+            database,
+            session,
+            resolution,
+            language.map(Language::locale),
+        )
+        .await
+        {
+            Ok(image) => image,
+            // Only a "not found" from every provider is worth remembering;
+            // timeouts and outages are tried again next time
+            Err(pokebase::Error::NotAvailable) => {
+                log::warn!("No image available for {id}", id = card.id.as_str());
+
+                let _ = fs::create_dir_all(cache.parent().unwrap_or(&cache)).await;
+                let _ = fs::write(&missing, b"").await;
+
+                return Ok(None);
+            }
+            Err(error) => Err(error)?,
+        };
+
+        let _ = fs::create_dir_all(cache.parent().unwrap_or(&cache)).await;
+        let _ = fs::write(&cache, &image.bytes).await;
+
+        Metadata::new(&image).save(&metadata).await;
+        cache::written().await;
+
+        Ok::<_, anywho::Error>(Some(image.bytes))
+    };
+
+    let revalidated = revalidate(
+        card, database, session, resolution, language, priority, &cache, &metadata,
+    );
+
+    // Warm images skip decoding entirely
+    if let Some(image) = Image::load(&processed, resolution).await {
+        cache::touch(&processed).await;
+
+        let Some(bytes) = revalidated.await else {
+            return Ok(Some(image));
+        };
+
+        let image = decode(card, resolution, bytes).await?;
+        image.save(&processed).await;
 
-            let fetch_from_cache = async {
-                let bytes = fs::read(&cache).await?;
+        return Ok(Some(image));
+    }
+
+    let bytes = match fs::read(&cache).await {
+        Ok(bytes) => {
+            cache::touch(&cache).await;
 
-                Ok(Bytes::from(bytes))
+            Bytes::from(bytes)
+        }
+        Err(_) => {
+            let Some(bytes) = download_image().await? else {
+                return Ok(None);
             };
 
-            let download_image = async {
-                let image = card::Image::download(&card, &database, &session).await?;
+            bytes
+        }
+    };
+
+    let bytes = revalidated.await.unwrap_or(bytes);
 
-                Ok::<_, anywho::Error>(image.bytes)
+    let image = match decode(card, resolution, bytes).await {
+        Ok(image) => image,
+        Err(error) => {
+            // Truncated or corrupt images are downloaded again
+            log::warn!(
+                "Invalid image of {id} in cache ({error}); downloading again...",
+                id = card.id.as_str()
+            );
+
+            cache::remove(&cache).await;
+
+            let Some(bytes) = download_image().await? else {
+                return Ok(None);
             };
 
-            let bytes = fetch_from_cache
-                .or_else(|_: anywho::Error| download_image)
-                .await?;
+            let image = decode(card, resolution, bytes).await;
 
-            if !fs::try_exists(&cache).await.unwrap_or_default() {
-                let _ = fs::create_dir_all(cache.parent().unwrap_or(&cache)).await;
-                let _ = fs::write(cache, &bytes).await;
+            if image.is_err() {
+                cache::remove(&cache).await;
             }
 
-            // Decode image as RGBA in a background blocking thread
-            task::spawn_blocking(move || {
-                let mut image = image::ImageReader::new(io::Cursor::new(bytes))
-                    .with_guessed_format()?
-                    .decode()?
-                    .to_rgba8();
+            image?
+        }
+    };
 
-                if !has_rounded_corners(&image) {
-                    log::warn!("Card without rounded corners: {id}", id = card.id.as_str());
-                    round_corners(&mut image);
-                }
+    image.save(&processed).await;
+
+    Ok(Some(image))
+}
+
+async fn decode(card: &Card, resolution: Resolution, bytes: Bytes) -> Result<Image, anywho::Error> {
+    let id = card.id.clone();
 
-                Ok(Image {
-                    width: image.width(),
-                    height: image.height(),
-                    rgba: Bytes::from(image.into_raw()),
-                })
-            })
-            .await?
+    // Decode image as RGBA in a background blocking thread
+    task::spawn_blocking(move || {
+        let mut image = image::ImageReader::new(io::Cursor::new(bytes))
+            .with_guessed_format()?
+            .decode()?;
+
+        let (width, height) = dimensions(resolution);
+
+        // Providers are free to serve bigger images than we display; scans
+        // and custom photos may not share the aspect ratio of a card, though
+        if image.width() > width || image.height() > height {
+            image = image.resize(width, height, image::imageops::FilterType::Triangle);
         }
-    }
+
+        let mut image = image.to_rgba8();
+
+        if !has_rounded_corners(&image) {
+            log::warn!("Card without rounded corners: {id}", id = id.as_str());
+            round_corners(&mut image);
+        }
+
+        Ok(Image {
+            resolution,
+            language: None,
+            width: image.width(),
+            height: image.height(),
+            rgba: Bytes::from(image.into_raw()),
+        })
+    })
+    .await?
 }
 
 impl fmt::Debug for Image {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Image")
+            .field("resolution", &self.resolution)
+            .field("language", &self.language)
             .field("width", &self.width)
             .field("height", &self.height)
             .field("rgba", &self.rgba.len())
@@ -92,11 +359,162 @@ impl fmt::Debug for Image {
     }
 }
 
-fn cache_dir() -> PathBuf {
-    dirs::cache_dir()
+// Cached images are validated against their provider once in a while;
+// unchanged images only cost a `304 Not Modified`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Metadata {
+    validator: Validator,
+    validated_at: SystemTime,
+}
+
+impl Metadata {
+    const MAX_AGE: Duration = Duration::from_secs(60 * 60 * 24 * 30);
+
+    fn new(image: &card::Image) -> Self {
+        Self {
+            validator: image.validator.clone(),
+            validated_at: SystemTime::now(),
+        }
+    }
+
+    async fn fetch(path: &Path) -> Option<Self> {
+        let metadata = fs::read_to_string(path).await.ok()?;
+
+        ron::from_str(&metadata).ok()
+    }
+
+    async fn save(&self, path: &Path) {
+        let Ok(metadata) = ron::to_string(self) else {
+            return;
+        };
+
+        let _ = fs::write(path, metadata).await;
+    }
+
+    fn is_outdated(&self) -> bool {
+        self.validated_at.elapsed().unwrap_or_default() > Self::MAX_AGE
+    }
+}
+
+// Returns the new image, if it changed since it was last validated
+async fn revalidate(
+    card: &Card,
+    database: &Database,
+    session: &Session,
+    resolution: Resolution,
+    language: Option<Language>,
+    priority: queue::Priority,
+    cache: &Path,
+    metadata: &Path,
+) -> Option<Bytes> {
+    let Some(mut current) = Metadata::fetch(metadata).await else {
+        // Images cached before validators were stored are considered fresh
+        Metadata {
+            validator: Validator::default(),
+            validated_at: SystemTime::now(),
+        }
+        .save(metadata)
+        .await;
+
+        return None;
+    };
+
+    if !current.is_outdated() {
+        return None;
+    }
+
+    let _permit = Queue::global().acquire(priority).await;
+
+    log::info!("Revalidating image of {id}", id = card.id.as_str());
+
+    match card::Image::revalidate(
+        card,
+        database,
+        session,
+        resolution,
+        language.map(Language::locale),
+        current.validator.provider.as_deref().unwrap_or_default(),
+        &current.validator,
+    )
+    .await
+    {
+        Ok(Some(image)) => {
+            let _ = fs::write(cache, &image.bytes).await;
+            Metadata::new(&image).save(metadata).await;
+
+            Some(image.bytes)
+        }
+        Ok(None) => {
+            current.validated_at = SystemTime::now();
+            current.save(metadata).await;
+
+            None
+        }
+        Err(error) => {
+            log::warn!(
+                "Failed to revalidate image of {id}: {error}",
+                id = card.id.as_str()
+            );
+
+            None
+        }
+    }
+}
+
+async fn is_missing(path: &Path) -> bool {
+    const MAX_AGE: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+
+    fs::metadata(path)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|marked_at| marked_at.elapsed().unwrap_or_default() < MAX_AGE)
+}
+
+fn dimensions(resolution: Resolution) -> (u32, u32) {
+    match resolution {
+        Resolution::Low => (245, 342),
+        Resolution::High => (Image::WIDTH, Image::HEIGHT),
+    }
+}
+
+// Images in the default language of a card are kept at the root
+fn cache_dir(resolution: Resolution, language: Option<Language>) -> PathBuf {
+    let cards = match language {
+        Some(language) => cache::dir().join(language.locale()),
+        None => cache::dir(),
+    };
+
+    match resolution {
+        Resolution::Low => cards.join("low"),
+        Resolution::High => cards,
+    }
+}
+
+fn custom_dir() -> PathBuf {
+    dirs::data_dir()
         .unwrap_or_default()
         .join(env!("CARGO_PKG_NAME"))
-        .join("cards")
+        .join("images")
+}
+
+fn custom_path(card: &Id) -> PathBuf {
+    custom_dir().join(format!("{id}.png", id = card.as_str()))
+}
+
+async fn normalize(source: &Path) -> Result<Vec<u8>, anywho::Error> {
+    let bytes = fs::read(source).await?;
+
+    task::spawn_blocking(move || {
+        let image = image::ImageReader::new(io::Cursor::new(bytes))
+            .with_guessed_format()?
+            .decode()?;
+
+        let mut png = io::Cursor::new(Vec::new());
+        image.write_to(&mut png, image::ImageFormat::Png)?;
+
+        Ok::<_, anywho::Error>(png.into_inner())
+    })
+    .await?
 }
 
 fn has_rounded_corners(rgba: &image::RgbaImage) -> bool {
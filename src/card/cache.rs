@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{self, AtomicUsize};
+use std::time::SystemTime;
+use tokio::fs;
+use tokio::task;
+
+pub const DEFAULT_LIMIT: u64 = 1024 * 1024 * 1024;
+
+// Scanning the whole cache is not free; only do it once in a while
+const EVICT_EVERY: usize = 100;
+
+pub fn dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_default()
+        .join(env!("CARGO_PKG_NAME"))
+        .join("cards")
+}
+
+pub fn limit() -> u64 {
+    env::var("HOLODECK_CACHE_LIMIT_MB")
+        .ok()
+        .and_then(|megabytes| megabytes.parse::<u64>().ok())
+        .map(|megabytes| megabytes * 1024 * 1024)
+        .unwrap_or(DEFAULT_LIMIT)
+}
+
+pub async fn touch(path: &Path) {
+    let path = path.to_owned();
+
+    let _ = task::spawn_blocking(move || {
+        std::fs::File::options()
+            .append(true)
+            .open(path)?
+            .set_modified(SystemTime::now())
+    })
+    .await;
+}
+
+pub async fn written() {
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+    if WRITES.fetch_add(1, atomic::Ordering::Relaxed) + 1 < EVICT_EVERY {
+        return;
+    }
+
+    WRITES.store(0, atomic::Ordering::Relaxed);
+
+    match evict(limit()).await {
+        Ok(0) => {}
+        Ok(freed) => log::info!("Evicted {freed} bytes of cached images"),
+        Err(error) => log::warn!("Failed to evict cached images: {error}"),
+    }
+}
+
+pub async fn remove(path: &Path) {
+    for extension in ["png", "ron", "rgba", "missing"] {
+        let _ = fs::remove_file(path.with_extension(extension)).await;
+    }
+}
+
+// Cached images of a card (and their metadata) share the same file stem and
+// are evicted together, least recently used first.
+pub async fn evict(limit: u64) -> io::Result<u64> {
+    struct Entry {
+        files: Vec<PathBuf>,
+        size: u64,
+        used_at: SystemTime,
+    }
+
+    let mut entries: HashMap<PathBuf, Entry> = HashMap::new();
+
+    for (path, metadata) in files(&dir()).await? {
+        let entry = entries
+            .entry(path.with_extension(""))
+            .or_insert_with(|| Entry {
+                files: Vec::new(),
+                size: 0,
+                used_at: SystemTime::UNIX_EPOCH,
+            });
+
+        entry.files.push(path);
+        entry.size += metadata.len();
+        entry.used_at = entry
+            .used_at
+            .max(metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH));
+    }
+
+    let mut total: u64 = entries.values().map(|entry| entry.size).sum();
+
+    if total <= limit {
+        return Ok(0);
+    }
+
+    let mut entries: Vec<_> = entries.into_values().collect();
+    entries.sort_by_key(|entry| entry.used_at);
+
+    let mut freed = 0;
+
+    for entry in entries {
+        if total <= limit {
+            break;
+        }
+
+        for file in &entry.files {
+            fs::remove_file(file).await?;
+        }
+
+        total -= entry.size;
+        freed += entry.size;
+    }
+
+    Ok(freed)
+}
+
+pub async fn clear() -> Result<u64, anywho::Error> {
+    let directory = dir();
+
+    let freed = files(&directory)
+        .await?
+        .iter()
+        .map(|(_path, metadata)| metadata.len())
+        .sum();
+
+    match fs::remove_dir_all(&directory).await {
+        Ok(()) => Ok(freed),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(error) => Err(error)?,
+    }
+}
+
+async fn files(directory: &Path) -> io::Result<Vec<(PathBuf, std::fs::Metadata)>> {
+    let mut files = Vec::new();
+    let mut directories = vec![directory.to_owned()];
+
+    while let Some(directory) = directories.pop() {
+        let mut entries = match fs::read_dir(&directory).await {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+            Err(error) => return Err(error),
+        };
+
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+
+            if metadata.is_dir() {
+                directories.push(entry.path());
+            } else {
+                files.push((entry.path(), metadata));
+            }
+        }
+    }
+
+    Ok(files)
+}
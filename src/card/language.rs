@@ -0,0 +1,67 @@
+use crate::pokebase::Card;
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Language {
+    English,
+    Japanese,
+    French,
+    German,
+    Italian,
+    Spanish,
+    Portuguese,
+}
+
+impl Language {
+    pub const ALL: &[Self] = &[
+        Self::English,
+        Self::Japanese,
+        Self::French,
+        Self::German,
+        Self::Italian,
+        Self::Spanish,
+        Self::Portuguese,
+    ];
+
+    pub fn locale(self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::Japanese => "ja",
+            Self::French => "fr",
+            Self::German => "de",
+            Self::Italian => "it",
+            Self::Spanish => "es",
+            Self::Portuguese => "pt",
+        }
+    }
+
+    // Returns `None` if the card would be shown in this language anyway,
+    // or if it was never printed in it.
+    pub fn for_card(self, card: &Card) -> Option<Self> {
+        let default = if card.name.has_english() {
+            Self::English
+        } else if card.name.has_japanese() {
+            Self::Japanese
+        } else {
+            return None;
+        };
+
+        (self != default && card.name.get(self.locale()).is_some()).then_some(self)
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::English => "English",
+            Self::Japanese => "Japanese",
+            Self::French => "French",
+            Self::German => "German",
+            Self::Italian => "Italian",
+            Self::Spanish => "Spanish",
+            Self::Portuguese => "Portuguese",
+        })
+    }
+}
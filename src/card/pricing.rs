@@ -1,16 +1,18 @@
 use crate::Collection;
 use crate::card;
+use crate::collection::{Amount, Wish};
 use crate::pokebase::card::pricing;
-use crate::pokebase::{Card, Database, Error, Session};
+use crate::pokebase::session::Validator;
+use crate::pokebase::set;
+use crate::pokebase::{Card, Database, Session};
 
-use anywho::anywho;
-use futures_util::{SinkExt, Stream, TryFutureExt};
+use futures_util::{SinkExt, Stream};
 use serde::{Deserialize, Serialize};
 use tokio::fs;
 use tokio::task;
 use tokio::time;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt;
 use std::ops;
 use std::path::PathBuf;
@@ -41,18 +43,31 @@ impl Map {
     }
 
     pub fn total_value(&self, collection: &Collection, rate: ExchangeRate) -> Value {
-        let prices = collection
-            .cards
-            .iter()
-            .filter_map(|(card, amount)| Some((self.get(card)?, amount)));
+        self.value(&collection.cards, rate)
+    }
+
+    pub fn value<'a>(
+        &self,
+        cards: impl IntoIterator<Item = (&'a card::Id, &'a Amount)>,
+        rate: ExchangeRate,
+    ) -> Value {
+        let prices: Vec<_> = cards
+            .into_iter()
+            .filter_map(|(card, amount)| Some((self.get(card)?, amount)))
+            .collect();
 
         let america = prices
-            .clone()
-            .map(|(pricing, amount)| pricing.value_in_dollars(amount.normal, amount.reverse, rate))
+            .iter()
+            .map(|(pricing, amount)| {
+                pricing.value_in_dollars(amount.normal(), amount.reverse(), rate)
+            })
             .fold(Dollars::ZERO, ops::Add::add);
 
         let europe = prices
-            .map(|(pricing, amount)| pricing.value_in_euros(amount.normal, amount.reverse, rate))
+            .iter()
+            .map(|(pricing, amount)| {
+                pricing.value_in_euros(amount.normal(), amount.reverse(), rate)
+            })
             .fold(Euros::ZERO, ops::Add::add);
 
         Value { america, europe }
@@ -86,6 +101,42 @@ impl Map {
         });
         cards.into_iter()
     }
+
+    // Wanted cards closest to their target price first; cards without
+    // a target or a known price go last.
+    pub fn wishlist<'a>(
+        &self,
+        collection: &'a Collection,
+        rate: ExchangeRate,
+    ) -> Vec<(&'a card::Id, &'a Wish, Option<Dollars>)> {
+        let mut wishlist: Vec<_> = collection
+            .wishlist
+            .iter()
+            .map(|(card, wish)| {
+                let price = self
+                    .get(card)
+                    .filter(|pricing| !pricing.missing)
+                    .map(|pricing| pricing.value_in_dollars(1, 0, rate))
+                    .filter(|price| *price != Dollars::ZERO);
+
+                (card, wish, price)
+            })
+            .collect();
+
+        let distance = |wish: &Wish, price: Option<Dollars>| match (wish.max_price, price) {
+            (Some(target), Some(price)) if target != Dollars::ZERO => {
+                price.amount() / target.amount()
+            }
+            (Some(_), Some(_)) => f64::MAX / 2.0,
+            _ => f64::MAX,
+        };
+
+        wishlist.sort_by(|(_, a, price_a), (_, b, price_b)| {
+            distance(a, *price_a).total_cmp(&distance(b, *price_b))
+        });
+
+        wishlist
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -107,6 +158,9 @@ pub struct Pricing {
     pub america: Variants<Dollars>,
     pub europe: Variants<Euros>,
     pub updated_at: SystemTime,
+    // When the providers were last asked; prices may not have changed since `updated_at`
+    pub checked_at: SystemTime,
+    pub missing: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -114,6 +168,20 @@ struct Cache {
     tcgplayer: pricing::tcgplayer::Pricing,
     cardmarket: pricing::cardmarket::Pricing,
     updated_at: SystemTime,
+    #[serde(default)]
+    checked_at: Option<SystemTime>,
+    #[serde(default)]
+    validator: Validator,
+    // Neither provider knows the card; it is asked again once outdated
+    #[serde(default)]
+    missing: bool,
+}
+
+impl Cache {
+    // Caches from before checks were tracked were checked when last updated
+    fn checked_at(&self) -> SystemTime {
+        self.checked_at.unwrap_or(self.updated_at)
+    }
 }
 
 impl Pricing {
@@ -127,8 +195,8 @@ impl Pricing {
             .flat_map(|collection| collection.cards.into_keys());
 
         for card in cards {
-            if let Ok(pricing) = Self::fetch_cache(&card).await {
-                prices.insert(card, pricing);
+            if let Ok(cache) = Self::fetch_cache(&card).await {
+                prices.insert(card, Self::from_cache(&cache));
             }
         }
 
@@ -143,58 +211,84 @@ impl Pricing {
         let session = session.clone();
 
         async move {
-            let fetch_from_cache = Self::fetch_cache(&card.id).and_then(async |cache| {
-                if is_outdated(cache.updated_at) {
-                    log::trace!("Pricing cache for {} is outdated", card.id.as_str());
-                    return Err(anywho!("Pricing cache for {card:?} is outdated"));
+            let cache = Self::fetch_cache(&card.id).await.ok();
+
+            if let Some(cache) = &cache {
+                if !is_outdated(cache.checked_at()) {
+                    return Ok(Self::from_cache(cache));
                 }
 
-                Ok(cache)
-            });
+                log::trace!("Pricing cache for {} is outdated", card.id.as_str());
+            }
 
-            let fetch_remotely = async {
-                let pricing = match pricing::Pricing::fetch(&card, &session).await {
-                    Ok(pricing) => Some(pricing),
-                    Err(Error::LocaleNotAvailable) => {
-                        log::warn!("Locale not available for {id}", id = card.id.as_str());
-                        None
-                    }
-                    Err(Error::RequestFailed(error))
-                        if error.status() == Some(reqwest::StatusCode::NOT_FOUND) =>
-                    {
-                        log::error!("Pricing for {id} not found", id = card.id.as_str());
-                        None
-                    }
-                    Err(error) => Err(error)?,
-                };
+            let validator = cache
+                .as_ref()
+                .map(|cache| cache.validator.clone())
+                .unwrap_or_default();
+
+            let cached = |cache: Option<Cache>| {
+                cache
+                    .filter(|cache| !cache.missing)
+                    .map(|cache| pricing::Pricing {
+                        tcgplayer: cache.tcgplayer,
+                        cardmarket: cache.cardmarket,
+                        updated_at: cache.updated_at,
+                        validator: cache.validator,
+                    })
+            };
 
-                let found = pricing.is_some();
-                let pricing = pricing.unwrap_or_default();
-
-                let cache = cache_dir().join(format!("{}.ron", card.id.as_str()));
-
-                if found || !fs::try_exists(&cache).await.unwrap_or(false) {
-                    let _ = fs::create_dir_all(cache.parent().unwrap_or(&cache)).await;
-                    let _ = fs::write(
-                        cache,
-                        ron::ser::to_string_pretty(
-                            &Cache {
-                                tcgplayer: pricing.tcgplayer,
-                                cardmarket: pricing.cardmarket,
-                                updated_at: SystemTime::now(),
-                            },
-                            ron::ser::PrettyConfig::default(),
-                        )
-                        .expect("Serialize pricing cache"),
-                    )
-                    .await;
+            let pricing = match pricing::Pricing::revalidate(&card, &session, &validator).await {
+                Ok(Some(pricing)) => Some(pricing),
+                Ok(None) => {
+                    log::debug!("Pricing for {id} has not changed", id = card.id.as_str());
+
+                    cached(cache)
                 }
+                Err(error) if error.is_not_found() => {
+                    log::warn!("Pricing for {id} not found: {error}", id = card.id.as_str());
 
-                Ok(Self::from_raw(pricing))
+                    // Known prices are kept around, even if they may not be current
+                    cached(cache)
+                }
+                Err(error) => Err(error)?,
             };
 
-            fetch_from_cache.or_else(|_| fetch_remotely).await
+            let missing = pricing.is_none();
+            let pricing = pricing.unwrap_or_default();
+
+            let checked_at = Self::save_cache(&card.id, &pricing, missing).await;
+
+            Ok(Self::from_raw(pricing, missing, checked_at))
+        }
+    }
+
+    // Prices of cards outside of `cards` are dropped, so the cache only grows with the collections
+    pub async fn fetch_set(
+        set: &set::Id,
+        cards: &BTreeSet<card::Id>,
+        database: &Database,
+        session: &Session,
+    ) -> Result<Vec<(card::Id, Self)>, anywho::Error> {
+        let prices = pricing::Pricing::fetch_set(set, database, session).await?;
+
+        let mut result = Vec::new();
+
+        for (card, mut pricing) in prices {
+            if !cards.contains(&card) {
+                continue;
+            }
+
+            // Bulk responses have no validator of their own; the one of the
+            // card still lets us revalidate it cheaply later
+            if let Ok(cache) = Self::fetch_cache(&card).await {
+                pricing.validator = cache.validator;
+            }
+
+            let checked_at = Self::save_cache(&card, &pricing, false).await;
+            result.push((card, Self::from_raw(pricing, false, checked_at)));
         }
+
+        Ok(result)
     }
 
     pub fn subscribe<'a>(
@@ -202,7 +296,7 @@ impl Pricing {
         session: &Session,
     ) -> impl Stream<Item = (card::Id, Pricing)> + 'a {
         let database = database.clone();
-        let session = session.clone();
+        let session = session.background();
 
         iced::stream::channel(1, async move |mut sender| {
             let mut prices = loop {
@@ -226,70 +320,159 @@ impl Pricing {
                     continue;
                 };
 
-                let cards = collections
+                // Owned and wanted cards
+                let tracked: BTreeSet<card::Id> = collections
                     .into_iter()
-                    .flat_map(|collection| collection.cards.into_keys().rev());
-
-                let mut i = 0;
-
-                let mut outdated_prices: Vec<_> = cards
-                    .filter_map(|card| {
-                        if prices
-                            .get(&card)
-                            .is_none_or(|price| is_outdated(price.updated_at))
-                        {
-                            database.cards.get(&card)
-                        } else {
-                            None
-                        }
+                    .flat_map(|collection| {
+                        collection
+                            .cards
+                            .into_keys()
+                            .chain(collection.wishlist.into_keys())
                     })
                     .collect();
 
-                if !outdated_prices.is_empty() {
-                    log::info!("Found {} outdated prices", outdated_prices.len());
-                }
+                let mut outdated_prices: BTreeMap<&set::Id, Vec<&Card>> = BTreeMap::new();
 
-                while let Some(card) = outdated_prices.pop() {
-                    if let Ok(pricing) = Pricing::fetch(card, &session).await {
-                        prices.insert(card.id.clone(), pricing);
-                        let _ = sender.send((card.id.clone(), pricing)).await;
+                for card in tracked
+                    .iter()
+                    .rev()
+                    .filter_map(|card| database.cards.get(card))
+                {
+                    if prices
+                        .get(&card.id)
+                        .is_none_or(|price| is_outdated(price.checked_at))
+                    {
+                        outdated_prices.entry(&card.set).or_default().push(card);
                     }
+                }
 
-                    i += 1;
+                let mut outdated = outdated_prices.values().map(Vec::len).sum::<usize>();
 
-                    if i % 10 == 0 {
-                        log::info!("Outdated prices left: {}", outdated_prices.len());
+                if outdated > 0 {
+                    log::info!(
+                        "Found {outdated} outdated prices in {} sets",
+                        outdated_prices.len()
+                    );
+                }
+
+                let mut is_budget_low = false;
+
+                for (set, mut cards) in outdated_prices {
+                    // Leave the remaining quota for foreground work
+                    if session.pokemon_tcg.limiter().is_budget_low().await {
+                        log::warn!(
+                            "PokemonTCG budget is running low; postponing {outdated} outdated prices"
+                        );
+
+                        is_budget_low = true;
+                        break;
                     }
 
-                    if i % 5 == 0 {
-                        time::sleep(Duration::from_secs(1)).await;
+                    let total = cards.len();
+
+                    // A single page of the whole set is cheaper than many cards
+                    if total > 1 {
+                        match Pricing::fetch_set(set, &tracked, &database, &session).await {
+                            Ok(set_prices) => {
+                                for (card, pricing) in set_prices {
+                                    cards.retain(|outdated| outdated.id != card);
+
+                                    prices.insert(card.clone(), pricing);
+                                    let _ = sender.send((card, pricing)).await;
+                                }
+                            }
+                            Err(error) => {
+                                log::warn!("Failed to fetch prices of {set}: {error}");
+                            }
+                        }
                     }
-                }
 
-                time::sleep(Duration::from_secs(30)).await;
-            }
-        })
-    }
+                    // Cards missing in bulk are fetched one by one
+                    for card in cards {
+                        if session.pokemon_tcg.limiter().is_budget_low().await {
+                            log::warn!(
+                                "PokemonTCG budget is running low; postponing {outdated} outdated prices"
+                            );
 
-    async fn fetch_cache(card: &card::Id) -> Result<Self, anywho::Error> {
-        let cache = cache_dir().join(format!("{}.ron", card.as_str()));
+                            is_budget_low = true;
+                            break;
+                        }
 
-        let pricing = fs::read_to_string(&cache).await?;
+                        if let Ok(pricing) = Pricing::fetch(card, &session).await {
+                            prices.insert(card.id.clone(), pricing);
+                            let _ = sender.send((card.id.clone(), pricing)).await;
+                        }
+                    }
 
-        let cache: Cache = task::spawn_blocking(move || ron::from_str(&pricing)).await??;
+                    if is_budget_low {
+                        break;
+                    }
+
+                    outdated -= total;
+                    log::info!("Outdated prices left: {outdated}");
+                }
 
-        Ok(Self::from_raw(pricing::Pricing {
-            tcgplayer: cache.tcgplayer,
-            cardmarket: cache.cardmarket,
-            updated_at: cache.updated_at,
-        }))
+                time::sleep(if is_budget_low {
+                    Duration::from_secs(60 * 60)
+                } else {
+                    Duration::from_secs(30)
+                })
+                .await;
+            }
+        })
     }
 
-    fn from_raw(pricing: pricing::Pricing) -> Self {
+    async fn fetch_cache(card: &card::Id) -> Result<Cache, anywho::Error> {
+        let pricing = fs::read_to_string(cache_path(card)).await?;
+
+        Ok(task::spawn_blocking(move || ron::from_str(&pricing)).await??)
+    }
+
+    // Returns the time of the check
+    async fn save_cache(card: &card::Id, pricing: &pricing::Pricing, missing: bool) -> SystemTime {
+        let cache = cache_path(card);
+        let checked_at = SystemTime::now();
+
+        let _ = fs::create_dir_all(cache.parent().unwrap_or(&cache)).await;
+        let _ = fs::write(
+            cache,
+            ron::ser::to_string_pretty(
+                &Cache {
+                    tcgplayer: pricing.tcgplayer,
+                    cardmarket: pricing.cardmarket,
+                    updated_at: pricing.updated_at,
+                    checked_at: Some(checked_at),
+                    validator: pricing.validator.clone(),
+                    missing,
+                },
+                ron::ser::PrettyConfig::default(),
+            )
+            .expect("Serialize pricing cache"),
+        )
+        .await;
+
+        checked_at
+    }
+
+    fn from_cache(cache: &Cache) -> Self {
+        Self::from_raw(
+            pricing::Pricing {
+                tcgplayer: cache.tcgplayer,
+                cardmarket: cache.cardmarket,
+                updated_at: cache.updated_at,
+                validator: cache.validator.clone(),
+            },
+            cache.missing,
+            cache.checked_at(),
+        )
+    }
+
+    fn from_raw(pricing: pricing::Pricing, missing: bool, checked_at: SystemTime) -> Self {
         let pricing::Pricing {
             tcgplayer,
             cardmarket,
             updated_at,
+            validator: _,
         } = pricing;
 
         let america = {
@@ -361,6 +544,8 @@ impl Pricing {
             america,
             europe,
             updated_at,
+            checked_at,
+            missing,
         }
     }
 
@@ -431,7 +616,8 @@ pub struct Spread<T> {
     pub market: T,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct Dollars {
     cents: u64,
 }
@@ -445,6 +631,16 @@ impl Dollars {
         }
     }
 
+    pub fn parse(dollars: &str) -> Option<Self> {
+        let dollars: f64 = dollars.trim().trim_start_matches('$').trim().parse().ok()?;
+
+        (dollars.is_finite() && dollars >= 0.0).then(|| Self::new(dollars))
+    }
+
+    pub fn amount(self) -> f64 {
+        self.cents as f64 / 100.0
+    }
+
     pub fn or_else(self, f: impl FnOnce() -> Dollars) -> Dollars {
         if self == Self::ZERO { f() } else { self }
     }
@@ -478,7 +674,7 @@ impl ops::Mul<u64> for Dollars {
 
 impl fmt::Display for Dollars {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "${:.2}", self.cents as f64 / 100.0)
+        write!(f, "${:.2}", self.amount())
     }
 }
 
@@ -539,31 +735,14 @@ pub struct ExchangeRate {
 }
 
 impl ExchangeRate {
-    pub async fn fetch() -> Result<Self, anywho::Error> {
-        #[derive(Deserialize)]
-        struct Response {
-            rates: Rates,
-        }
-
-        #[derive(Deserialize)]
-        struct Rates {
-            #[serde(rename = "EUR")]
-            eur: f64,
-        }
-
+    pub async fn fetch(session: &Session) -> Result<Self, anywho::Error> {
         log::info!("Fetching USD/EUR exchange rate...");
 
-        let response: Response = reqwest::get("https://api.frankfurter.app/latest?from=USD&to=EUR")
-            .await?
-            .error_for_status()?
-            .json()
-            .await?;
+        let usd_to_eur = session.frankfurter.fetch_rate("USD", "EUR").await?;
 
-        log::info!("USD/EUR exchange rate is {:.2}", response.rates.eur);
+        log::info!("USD/EUR exchange rate is {usd_to_eur:.2}");
 
-        Ok(Self {
-            usd_to_eur: response.rates.eur,
-        })
+        Ok(Self { usd_to_eur })
     }
 }
 
@@ -580,7 +759,11 @@ fn cache_dir() -> PathBuf {
         .join("prices")
 }
 
-fn is_outdated(updated_at: SystemTime) -> bool {
+fn cache_path(card: &card::Id) -> PathBuf {
+    cache_dir().join(format!("{}.ron", card.as_str()))
+}
+
+fn is_outdated(checked_at: SystemTime) -> bool {
     const WEEK: Duration = Duration::from_secs(60 * 60 * 24 * 7);
-    updated_at.elapsed().unwrap_or_default() > WEEK
+    checked_at.elapsed().unwrap_or_default() > WEEK
 }
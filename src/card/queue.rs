@@ -0,0 +1,151 @@
+use std::cmp;
+use std::collections::BinaryHeap;
+use std::env;
+use std::sync::{Arc, LazyLock, Mutex};
+use tokio::sync::oneshot;
+
+// A shared queue that limits the amount of concurrent downloads.
+//
+// Waiting downloads are served by priority and, then, most recent first; since
+// the last requested cards are the ones the user is most likely looking at.
+// Dropping a waiting future cancels its place in the queue.
+#[derive(Debug, Clone)]
+pub struct Queue {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Background,
+    Visible,
+}
+
+#[derive(Debug)]
+struct State {
+    available: usize,
+    sequence: u64,
+    waiting: BinaryHeap<Waiter>,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    priority: Priority,
+    sequence: u64,
+    sender: oneshot::Sender<Permit>,
+}
+
+#[derive(Debug)]
+pub struct Permit {
+    queue: Option<Queue>,
+}
+
+impl Queue {
+    pub const DEFAULT_CONCURRENCY: usize = 4;
+
+    pub fn new(concurrency: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                available: concurrency.max(1),
+                sequence: 0,
+                waiting: BinaryHeap::new(),
+            })),
+        }
+    }
+
+    pub fn global() -> &'static Self {
+        static QUEUE: LazyLock<Queue> = LazyLock::new(|| {
+            let concurrency = env::var("HOLODECK_MAX_DOWNLOADS")
+                .ok()
+                .and_then(|concurrency| concurrency.parse().ok())
+                .unwrap_or(Queue::DEFAULT_CONCURRENCY);
+
+            log::info!("Downloading at most {concurrency} images at once");
+
+            Queue::new(concurrency)
+        });
+
+        &QUEUE
+    }
+
+    pub async fn acquire(&self, priority: Priority) -> Permit {
+        let receiver = {
+            let mut state = self.state.lock().expect("Lock download queue");
+
+            if state.available > 0 {
+                state.available -= 1;
+
+                return Permit {
+                    queue: Some(self.clone()),
+                };
+            }
+
+            let (sender, receiver) = oneshot::channel();
+
+            state.sequence += 1;
+
+            let sequence = state.sequence;
+
+            state.waiting.push(Waiter {
+                priority,
+                sequence,
+                sender,
+            });
+
+            receiver
+        };
+
+        receiver
+            .await
+            .expect("Waiters are never dropped by the queue")
+    }
+
+    fn release(&self) {
+        let mut state = self.state.lock().expect("Lock download queue");
+
+        while let Some(waiter) = state.waiting.pop() {
+            let permit = Permit {
+                queue: Some(self.clone()),
+            };
+
+            // The waiter was cancelled; try the next one
+            if let Err(mut permit) = waiter.sender.send(permit) {
+                permit.queue = None;
+                continue;
+            }
+
+            return;
+        }
+
+        state.available += 1;
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(queue) = self.queue.take() {
+            queue.release();
+        }
+    }
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == cmp::Ordering::Equal
+    }
+}
+
+impl Eq for Waiter {}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then(self.sequence.cmp(&other.sequence))
+    }
+}
@@ -0,0 +1,178 @@
+use crate::card::{self, Image, Language, Resolution};
+
+use iced::widget::image;
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+
+// The decoded images of the whole application, shared by every screen.
+// Images are kept per language, so switching back and forth is instant.
+//
+// Once the memory budget is exceeded, the least recently viewed images are
+// dropped; screens simply fetch them again from the disk cache if needed.
+pub struct Store {
+    images: HashMap<(card::Id, Option<Language>, Resolution), Entry>,
+    used: usize,
+    budget: usize,
+    clock: Cell<u64>,
+}
+
+struct Entry {
+    handle: image::Handle,
+    size: usize,
+    viewed_at: Cell<u64>,
+}
+
+impl Store {
+    pub const DEFAULT_BUDGET: usize = 512 * 1024 * 1024;
+
+    pub fn new() -> Self {
+        let budget = env::var("HOLODECK_MEMORY_LIMIT_MB")
+            .ok()
+            .and_then(|megabytes| megabytes.parse::<usize>().ok())
+            .map(|megabytes| megabytes * 1024 * 1024)
+            .unwrap_or(Self::DEFAULT_BUDGET);
+
+        Self::with_budget(budget)
+    }
+
+    pub fn with_budget(budget: usize) -> Self {
+        Self {
+            images: HashMap::new(),
+            used: 0,
+            budget,
+            clock: Cell::new(0),
+        }
+    }
+
+    pub fn insert(&mut self, card: card::Id, image: Image) -> image::Handle {
+        let size = image.rgba.len();
+        let handle = image::Handle::from_rgba(image.width, image.height, image.rgba);
+
+        let entry = Entry {
+            handle: handle.clone(),
+            size,
+            viewed_at: Cell::new(self.tick()),
+        };
+
+        if let Some(old) = self
+            .images
+            .insert((card, image.language, image.resolution), entry)
+        {
+            self.used -= old.size;
+        }
+
+        self.used += size;
+        self.evict();
+
+        handle
+    }
+
+    pub fn get(
+        &self,
+        card: &card::Id,
+        language: Option<Language>,
+        resolution: Resolution,
+    ) -> Option<&image::Handle> {
+        let entry = self.images.get(&(card.clone(), language, resolution))?;
+        entry.viewed_at.set(self.tick());
+
+        Some(&entry.handle)
+    }
+
+    pub fn best(
+        &self,
+        card: &card::Id,
+        language: Option<Language>,
+    ) -> Option<(&image::Handle, Resolution)> {
+        [Resolution::High, Resolution::Low]
+            .into_iter()
+            .find_map(|resolution| Some((self.get(card, language, resolution)?, resolution)))
+    }
+
+    // Removes the images of a card in every language
+    pub fn remove(&mut self, card: &card::Id) {
+        let mut freed = 0;
+
+        self.images.retain(|(id, _, _), entry| {
+            let is_removed = id == card;
+
+            if is_removed {
+                freed += entry.size;
+            }
+
+            !is_removed
+        });
+
+        self.used -= freed;
+    }
+
+    pub fn contains(&self, card: &card::Id, language: Option<Language>) -> bool {
+        [Resolution::High, Resolution::Low]
+            .into_iter()
+            .any(|resolution| {
+                self.images
+                    .contains_key(&(card.clone(), language, resolution))
+            })
+    }
+
+    fn tick(&self) -> u64 {
+        let now = self.clock.get() + 1;
+        self.clock.set(now);
+
+        now
+    }
+
+    fn evict(&mut self) {
+        if self.used <= self.budget {
+            return;
+        }
+
+        let mut images: Vec<_> = self
+            .images
+            .iter()
+            .map(|(key, entry)| (entry.viewed_at.get(), key.clone()))
+            .collect();
+
+        images.sort_unstable_by_key(|(viewed_at, _)| *viewed_at);
+
+        // The most recent image is always kept
+        let _ = images.pop();
+
+        let mut evicted = 0;
+
+        for (_, key) in images {
+            if self.used <= self.budget {
+                break;
+            }
+
+            if let Some(entry) = self.images.remove(&key) {
+                self.used -= entry.size;
+                evicted += 1;
+            }
+        }
+
+        log::debug!(
+            "Evicted {evicted} images from memory ({used} bytes in use)",
+            used = self.used
+        );
+    }
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Store {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Store")
+            .field("images", &self.images.len())
+            .field("used", &self.used)
+            .field("budget", &self.budget)
+            .finish()
+    }
+}
@@ -1,94 +1,394 @@
+pub mod exchange;
+
+use crate::card::Language;
+use crate::card::pricing::Dollars;
 use crate::pokebase::card;
 use crate::pokebase::pokemon;
 use crate::pokebase::{Card, Database, Pokemon};
 
-use num_traits::Zero;
 use serde::{Deserialize, Serialize};
-use std::cell::RefCell;
-use std::collections::{BTreeMap, BTreeSet};
-use std::path::PathBuf;
+use std::cell::{Ref, RefCell};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::sync::atomic::{self, AtomicU64};
+use std::time::SystemTime;
 use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Collection {
     pub name: Name,
     pub cards: BTreeMap<card::Id, Amount>,
+    // The language images are shown in, if available
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<Language>,
+    // The cards we want, with the most we would pay for them
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub wishlist: BTreeMap<card::Id, Wish>,
+    // Every change to the quantities, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<Event>,
+    // Who is making changes; profiles are often shared by a whole family
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collector: Option<String>,
 
     #[serde(skip)]
     total_pokemon: RefCell<Option<usize>>,
     #[serde(skip)]
     rarest_card_by_pokemon: RefCell<BTreeMap<pokemon::Id, Option<card::Id>>>,
+    #[serde(skip)]
+    surplus: RefCell<Option<Vec<(card::Id, Amount)>>>,
+    #[serde(skip)]
+    recently_added: RefCell<Option<Vec<card::Id>>>,
 }
 
 impl Collection {
-    pub async fn create(name: Name) -> Result<Self, anywho::Error> {
-        let collection = Self {
+    pub fn new(name: Name) -> Self {
+        Self {
             name,
             cards: BTreeMap::new(),
+            language: None,
+            wishlist: BTreeMap::new(),
+            events: Vec::new(),
+            collector: None,
             rarest_card_by_pokemon: RefCell::new(BTreeMap::new()),
             total_pokemon: RefCell::new(None),
-        };
+            surplus: RefCell::new(None),
+            recently_added: RefCell::new(None),
+        }
+    }
 
-        let _ = collection.save().await;
+    pub async fn create(name: Name) -> Result<Self, anywho::Error> {
+        let collection = Self::new(name);
+
+        let _saves = SAVES.lock().await;
+        ensure_available(&collection.name).await?;
+        write(&collection).await?;
 
         Ok(collection)
     }
 
     pub async fn list() -> Result<Vec<Self>, anywho::Error> {
-        if !fs::try_exists(collections_path()).await? {
+        migrate().await?;
+
+        if !fs::try_exists(collections_dir()).await? {
             return Ok(Vec::new());
         }
 
-        Ok(ron::from_str(
-            &fs::read_to_string(collections_path()).await?,
-        )?)
+        let _saves = SAVES.lock().await;
+
+        let mut paths = Vec::new();
+        let mut entries = fs::read_dir(collections_dir()).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+
+            if path.extension().is_some_and(|extension| extension == "ron") {
+                paths.push(path);
+            }
+        }
+
+        let mut collections: Vec<Self> = Vec::new();
+
+        for path in paths {
+            match ron::from_str::<Self>(&fs::read_to_string(&path).await?) {
+                Ok(collection) if path == collection_path(&collection.name) => {
+                    collections.push(collection);
+                }
+                Ok(collection) => collections.push(relocate(collection, &path).await?),
+                Err(error) => log::error!("Failed to load {}: {error}", path.display()),
+            }
+        }
+
+        collections.sort_by(|a, b| a.name.as_str().cmp(b.name.as_str()));
+
+        Ok(collections)
+    }
+
+    pub async fn rename(mut self, name: Name) -> Result<Self, anywho::Error> {
+        let mut saves = SAVES.lock().await;
+
+        // Only changing the case keeps the same file
+        let is_recased = name.collides(&self.name);
+
+        if !is_recased {
+            ensure_available(&name).await?;
+        }
+
+        let old = std::mem::replace(&mut self.name, name);
+
+        write(&self).await?;
+
+        if !is_recased {
+            fs::remove_file(collection_path(&old)).await?;
+        }
+
+        forget(&mut saves, old);
+
+        Ok(self)
+    }
+
+    pub async fn duplicate(mut self, name: Name) -> Result<Self, anywho::Error> {
+        let _saves = SAVES.lock().await;
+        ensure_available(&name).await?;
+
+        self.name = name;
+        write(&self).await?;
+
+        Ok(self)
+    }
+
+    // Moves every copy of the other collection into this one and deletes it
+    pub async fn merge(mut self, other: Self) -> Result<Self, anywho::Error> {
+        let other_name = other.name.clone();
+        self.absorb(other);
+
+        let mut saves = SAVES.lock().await;
+
+        write(&self).await?;
+        fs::remove_file(collection_path(&other_name)).await?;
+        forget(&mut saves, other_name);
+
+        Ok(self)
+    }
+
+    // The copies keep their history; they were not added just now
+    fn absorb(&mut self, other: Self) {
+        for (card, amount) in other.cards {
+            self.cards
+                .entry(card)
+                .or_default()
+                .copies
+                .extend(amount.copies);
+        }
+
+        for (card, wish) in other.wishlist {
+            let _ = self.wishlist.entry(card).or_insert(wish);
+        }
+
+        if self.language.is_none() {
+            self.language = other.language;
+        }
+
+        self.events.extend(other.events);
+        self.events.sort_by_key(|event| event.at);
+        self.invalidate();
     }
 
-    pub fn add(&mut self, card: card::Id, variant: Variant) {
-        let amount = self.cards.entry(card).or_default();
+    pub async fn delete(self) -> Result<(), anywho::Error> {
+        let mut saves = SAVES.lock().await;
+
+        fs::remove_file(collection_path(&self.name)).await?;
+        forget(&mut saves, self.name);
+
+        Ok(())
+    }
+
+    // New copies have an unknown language; `Collection::language` falls back
+    // to the preference of the collection for them
+    pub fn add(&mut self, card: card::Id, variant: Variant) -> Change {
+        self.edit(card, |amount| {
+            amount.copies.push(Item {
+                variant,
+                ..Item::default()
+            });
+        })
+    }
+
+    pub fn remove(&mut self, card: card::Id, copy: usize) -> Change {
+        self.edit(card, |amount| {
+            if copy < amount.copies.len() {
+                let _ = amount.copies.remove(copy);
+            }
+        })
+    }
+
+    pub fn set_quantity(&mut self, card: card::Id, variant: Variant, quantity: usize) -> Change {
+        self.edit(card, |amount| {
+            let current = amount.count(variant);
+
+            if quantity > current {
+                amount.copies.extend(std::iter::repeat_n(
+                    Item {
+                        variant,
+                        ..Item::default()
+                    },
+                    quantity - current,
+                ));
+            } else {
+                // The most recent copies go first
+                let mut excess = current - quantity;
 
-        match variant {
-            Variant::Normal => amount.normal += 1,
-            Variant::Reverse => amount.reverse += 1,
+                while excess > 0 {
+                    let Some(last) = amount
+                        .copies
+                        .iter()
+                        .rposition(|copy| copy.variant == variant)
+                    else {
+                        break;
+                    };
+
+                    let _ = amount.copies.remove(last);
+                    excess -= 1;
+                }
+            }
+        })
+    }
+
+    pub fn import(&mut self, matches: &[exchange::Match]) -> Vec<Change> {
+        matches
+            .iter()
+            .map(|entry| {
+                self.edit(entry.card.clone(), |amount| {
+                    amount.copies.extend(std::iter::repeat_n(
+                        Item {
+                            variant: entry.variant,
+                            ..Item::default()
+                        },
+                        entry.quantity,
+                    ));
+                })
+            })
+            .collect()
+    }
+
+    // Every mutation of the cards goes through here, so it can be undone
+    pub fn edit(&mut self, card: card::Id, f: impl FnOnce(&mut Amount)) -> Change {
+        let before = self.cards.get(&card).cloned();
+
+        let mut amount = before.clone().unwrap_or_default();
+        f(&mut amount);
+
+        let after = (amount.total() > 0).then_some(amount);
+
+        let change = Change {
+            card,
+            before,
+            after,
+        };
+
+        self.replace(&change.card, change.after.clone());
+
+        change
+    }
+
+    fn replace(&mut self, card: &card::Id, amount: Option<Amount>) {
+        let before = match amount {
+            Some(amount) => self.cards.insert(card.clone(), amount),
+            None => self.cards.remove(card),
+        };
+
+        let at = Timestamp::now();
+        let author = self.collector.clone();
+
+        for &variant in Variant::ALL {
+            let count = |amount: Option<&Amount>| {
+                amount
+                    .map(|amount| amount.count(variant))
+                    .unwrap_or_default()
+            };
+
+            let from = count(before.as_ref());
+            let to = count(self.cards.get(card));
+
+            if from == to {
+                continue;
+            }
+
+            self.events.push(Event {
+                at,
+                author: author.clone(),
+                card: card.clone(),
+                variant,
+                kind: event::Kind::new(from, to),
+            });
         }
 
+        self.invalidate();
+    }
+
+    fn invalidate(&self) {
         *self.total_pokemon.borrow_mut() = None;
         self.rarest_card_by_pokemon.borrow_mut().clear();
+        *self.surplus.borrow_mut() = None;
+        *self.recently_added.borrow_mut() = None;
     }
 
     pub fn save<'a>(&self) -> impl Future<Output = Result<(), anywho::Error>> + 'a {
         let collection = self.clone();
+        let generation = GENERATION.fetch_add(1, atomic::Ordering::Relaxed) + 1;
 
         async move {
-            let mut collections = Self::list().await?;
+            let mut saved = SAVES.lock().await;
 
-            if let Some(old) = collections
-                .iter_mut()
-                .find(|candidate| candidate.name == collection.name)
+            if saved
+                .get(&collection.name)
+                .is_some_and(|latest| *latest > generation)
             {
-                *old = collection;
-            } else {
-                collections.push(collection);
+                return Ok(());
             }
 
-            fs::create_dir_all(data_dir()).await?;
-
-            fs::write(
-                collections_path(),
-                ron::ser::to_string_pretty(&collections, ron::ser::PrettyConfig::default())?,
-            )
-            .await?;
+            write(&collection).await?;
+            let _ = saved.insert(collection.name.clone(), generation);
 
             Ok(())
         }
     }
 
+    // The language of the copies we own wins over the preference of the collection
+    pub fn language(&self, card: &card::Id) -> Option<Language> {
+        self.cards
+            .get(card)
+            .and_then(|amount| amount.copies.iter().find_map(|copy| copy.language))
+            .or(self.language)
+    }
+
+    pub fn is_wanted(&self, card: &card::Id) -> bool {
+        self.wishlist.contains_key(card)
+    }
+
+    pub fn want(&mut self, card: card::Id, max_price: Option<Dollars>) {
+        let _ = self.wishlist.insert(card, Wish { max_price });
+    }
+
+    pub fn unwant(&mut self, card: &card::Id) {
+        let _ = self.wishlist.remove(card);
+    }
+
+    // The most recently added cards first, without duplicates
+    pub fn recently_added(&self) -> Ref<'_, [card::Id]> {
+        if self.recently_added.borrow().is_none() {
+            let mut seen = BTreeSet::new();
+
+            let recent = self
+                .events
+                .iter()
+                .rev()
+                .filter(|event| event.kind.is_addition())
+                .map(|event| &event.card)
+                .filter(|card| self.cards.contains_key(*card) && seen.insert(*card))
+                .cloned()
+                .collect();
+
+            *self.recently_added.borrow_mut() = Some(recent);
+        }
+
+        Ref::map(self.recently_added.borrow(), |recent| {
+            recent.as_deref().unwrap_or_default()
+        })
+    }
+
     pub fn unique_cards(&self) -> usize {
         self.cards.len()
     }
 
     pub fn total_cards(&self) -> usize {
-        self.cards.values().copied().map(Amount::total).sum()
+        self.cards.values().map(Amount::total).sum()
     }
 
     pub fn total_pokemon(&self, database: &Database) -> usize {
@@ -121,6 +421,67 @@ impl Collection {
         rares.into_iter()
     }
 
+    // The copies we could trade away without leaving a hole in the binders;
+    // i.e. every copy of a card but the best one, and every print of a
+    // Pokémon that is already filled by a rarer card.
+    pub fn surplus(&self, database: &Database) -> Ref<'_, [(card::Id, Amount)]> {
+        if self.surplus.borrow().is_none() {
+            let surplus = self.compute_surplus(database);
+            *self.surplus.borrow_mut() = Some(surplus);
+        }
+
+        Ref::map(self.surplus.borrow(), |surplus| {
+            surplus.as_deref().unwrap_or_default()
+        })
+    }
+
+    fn compute_surplus(&self, database: &Database) -> Vec<(card::Id, Amount)> {
+        let mut surplus: Vec<_> = self
+            .cards
+            .iter()
+            .filter_map(|(id, amount)| {
+                let card = database.cards.get(id)?;
+
+                // Trainers and energies are not part of any binder, but one is enough
+                let is_filling = card.pokedex.is_empty()
+                    || card.pokedex.iter().any(|pokemon| {
+                        database
+                            .pokemon
+                            .get(pokemon)
+                            .and_then(|pokemon| self.rarest_card_for(pokemon, database))
+                            .is_some_and(|rarest| rarest.id == *id)
+                    });
+
+                let mut copies = amount.copies.clone();
+
+                if is_filling {
+                    // The copy in the best condition stays
+                    let best = copies
+                        .iter()
+                        .enumerate()
+                        .min_by_key(|(_, copy)| (copy.condition, Reverse(copy.grade)))
+                        .map(|(index, _)| index)?;
+
+                    let _ = copies.remove(best);
+                }
+
+                (!copies.is_empty()).then(|| (id.clone(), Amount { copies }))
+            })
+            .collect();
+
+        surplus.sort_by_key(|(id, _)| {
+            Reverse(
+                database
+                    .cards
+                    .get(id)
+                    .map(|card| card.rarity)
+                    .unwrap_or_default(),
+            )
+        });
+
+        surplus
+    }
+
     pub fn rarest_card_for<'a>(
         &self,
         pokemon: &Pokemon,
@@ -155,6 +516,181 @@ impl Collection {
     }
 }
 
+// The state of a card in a collection, before and after an edit.
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub card: card::Id,
+    before: Option<Amount>,
+    after: Option<Amount>,
+}
+
+impl Change {
+    pub fn is_empty(&self) -> bool {
+        self.before == self.after
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    undo: Vec<Change>,
+    redo: Vec<Change>,
+    is_amending: bool,
+}
+
+impl History {
+    const LIMIT: usize = 100;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, change: Change) {
+        if change.is_empty() {
+            return;
+        }
+
+        self.redo.clear();
+        self.undo.push(change);
+        self.is_amending = false;
+
+        if self.undo.len() > Self::LIMIT {
+            let _ = self.undo.remove(0);
+        }
+    }
+
+    // Consecutive amendments of the same card are merged; e.g. while typing notes
+    pub fn amend(&mut self, change: Change) {
+        match self.undo.last_mut() {
+            Some(last) if self.is_amending && last.card == change.card => {
+                last.after = change.after;
+            }
+            _ => self.push(change),
+        }
+
+        self.is_amending = true;
+    }
+
+    pub fn undo(&mut self, collection: &mut Collection) -> Option<card::Id> {
+        let change = self.undo.pop()?;
+        self.is_amending = false;
+        collection.replace(&change.card, change.before.clone());
+
+        let card = change.card.clone();
+        self.redo.push(change);
+
+        Some(card)
+    }
+
+    pub fn redo(&mut self, collection: &mut Collection) -> Option<card::Id> {
+        let change = self.redo.pop()?;
+        self.is_amending = false;
+        collection.replace(&change.card, change.after.clone());
+
+        let card = change.card.clone();
+        self.undo.push(change);
+
+        Some(card)
+    }
+
+    pub fn next_undo(&self) -> Option<&card::Id> {
+        self.undo.last().map(|change| &change.card)
+    }
+
+    pub fn next_redo(&self) -> Option<&card::Id> {
+        self.redo.last().map(|change| &change.card)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Wish {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_price: Option<Dollars>,
+}
+
+// A change to the quantity of a variant of a card.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Event {
+    pub at: Timestamp,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub card: card::Id,
+    pub variant: Variant,
+    pub kind: event::Kind,
+}
+
+pub mod event {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum Kind {
+        Added { quantity: usize },
+        Removed { quantity: usize },
+        QuantityChanged { from: usize, to: usize },
+    }
+
+    impl Kind {
+        pub fn new(from: usize, to: usize) -> Self {
+            if from == 0 {
+                Self::Added { quantity: to }
+            } else if to == 0 {
+                Self::Removed { quantity: from }
+            } else {
+                Self::QuantityChanged { from, to }
+            }
+        }
+
+        pub fn before(self) -> usize {
+            match self {
+                Self::Added { .. } => 0,
+                Self::Removed { quantity } => quantity,
+                Self::QuantityChanged { from, .. } => from,
+            }
+        }
+
+        pub fn after(self) -> usize {
+            match self {
+                Self::Added { quantity } => quantity,
+                Self::Removed { .. } => 0,
+                Self::QuantityChanged { to, .. } => to,
+            }
+        }
+
+        pub fn is_addition(self) -> bool {
+            self.after() > self.before()
+        }
+    }
+}
+
+// Seconds since the UNIX epoch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+    pub fn now() -> Self {
+        Self(
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        )
+    }
+
+    pub fn date(self) -> Date {
+        Date::from_days((self.0 / SECONDS_PER_DAY) as i64)
+    }
+}
+
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct Name(String);
@@ -171,30 +707,490 @@ impl Name {
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    // Names that only differ in case would share a file on most systems
+    pub fn collides(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+
+    fn key(&self) -> String {
+        self.0.to_lowercase()
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
 }
 
-#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+// Every copy we own of a card, one record each.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "Stored")]
 pub struct Amount {
-    #[serde(default, skip_serializing_if = "usize::is_zero")]
-    pub normal: usize,
-    #[serde(default, skip_serializing_if = "usize::is_zero")]
-    pub reverse: usize,
+    pub copies: Vec<Item>,
 }
 
 impl Amount {
-    pub fn total(self) -> usize {
-        self.normal + self.reverse
+    pub fn total(&self) -> usize {
+        self.copies.len()
+    }
+
+    pub fn normal(&self) -> usize {
+        self.count(Variant::Normal)
+    }
+
+    pub fn reverse(&self) -> usize {
+        self.count(Variant::Reverse)
+    }
+
+    pub fn count(&self, variant: Variant) -> usize {
+        self.copies
+            .iter()
+            .filter(|copy| copy.variant == variant)
+            .count()
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// Collections used to only count copies per variant; those counters are
+// turned into plain copies when loaded.
+#[derive(Deserialize)]
+struct Stored {
+    #[serde(default)]
+    copies: Vec<Item>,
+    #[serde(default)]
+    normal: usize,
+    #[serde(default)]
+    reverse: usize,
+}
+
+impl From<Stored> for Amount {
+    fn from(stored: Stored) -> Self {
+        let mut copies = stored.copies;
+
+        let legacy = std::iter::repeat_n(Variant::Normal, stored.normal)
+            .chain(std::iter::repeat_n(Variant::Reverse, stored.reverse))
+            .map(|variant| Item {
+                variant,
+                acquired: None,
+                ..Item::default()
+            });
+
+        copies.extend(legacy);
+
+        Self { copies }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Item {
+    pub variant: Variant,
+    #[serde(default)]
+    pub condition: Condition,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<Language>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grade: Option<Grade>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub company: Option<Company>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acquired: Option<Date>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    // A photo of this very copy, relative to the custom images
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub photo: Option<PathBuf>,
+}
+
+impl Default for Item {
+    fn default() -> Self {
+        Self {
+            variant: Variant::Normal,
+            condition: Condition::default(),
+            language: None,
+            grade: None,
+            company: None,
+            acquired: Some(Date::today()),
+            notes: String::new(),
+            photo: None,
+        }
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} · {}", self.variant, self.condition)?;
+
+        if let Some(language) = self.language {
+            write!(f, " · {language}")?;
+        }
+
+        match (self.company, self.grade) {
+            (Some(company), Some(grade)) => write!(f, " · {company} {grade}"),
+            (None, Some(grade)) => write!(f, " · {grade}"),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Variant {
     Normal,
     Reverse,
 }
 
-fn collections_path() -> PathBuf {
-    data_dir().join("collections.ron")
+impl Variant {
+    pub const ALL: &[Self] = &[Self::Normal, Self::Reverse];
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Normal => "Normal",
+            Self::Reverse => "Reverse Holo",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub enum Condition {
+    Mint,
+    #[default]
+    NearMint,
+    Excellent,
+    Good,
+    LightPlayed,
+    Played,
+    Poor,
+}
+
+impl Condition {
+    pub const ALL: &[Self] = &[
+        Self::Mint,
+        Self::NearMint,
+        Self::Excellent,
+        Self::Good,
+        Self::LightPlayed,
+        Self::Played,
+        Self::Poor,
+    ];
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Mint => "Mint",
+            Self::NearMint => "Near Mint",
+            Self::Excellent => "Excellent",
+            Self::Good => "Good",
+            Self::LightPlayed => "Light Played",
+            Self::Played => "Played",
+            Self::Poor => "Poor",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Company {
+    Psa,
+    Bgs,
+    Cgc,
+    Ace,
+    Other,
+}
+
+impl Company {
+    pub const ALL: &[Self] = &[Self::Psa, Self::Bgs, Self::Cgc, Self::Ace, Self::Other];
+}
+
+impl fmt::Display for Company {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Psa => "PSA",
+            Self::Bgs => "BGS",
+            Self::Cgc => "CGC",
+            Self::Ace => "ACE",
+            Self::Other => "Other",
+        })
+    }
+}
+
+// A grade from 1 to 10, in half points (e.g. 9.5)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Grade(u8);
+
+impl Grade {
+    pub fn parse(grade: &str) -> Option<Self> {
+        let grade: f32 = grade.trim().parse().ok()?;
+        let halves = (grade * 2.0).round();
+
+        (2.0..=20.0).contains(&halves).then_some(Self(halves as u8))
+    }
+}
+
+impl fmt::Display for Grade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_multiple_of(2) {
+            write!(f, "{}", self.0 / 2)
+        } else {
+            write!(f, "{}.5", self.0 / 2)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Date {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+impl Date {
+    pub fn today() -> Self {
+        Timestamp::now().date()
+    }
+
+    pub fn parse(date: &str) -> Option<Self> {
+        let mut parts = date.trim().splitn(3, '-');
+
+        let year: u16 = parts.next()?.parse().ok()?;
+        let month = parts.next()?.parse().ok()?;
+        let day = parts.next()?.parse().ok()?;
+
+        let is_leap =
+            year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+
+        let days = match month {
+            2 if is_leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            1..=12 => 31,
+            _ => return None,
+        };
+
+        (1..=days)
+            .contains(&day)
+            .then_some(Self { year, month, day })
+    }
+
+    // Days since the UNIX epoch to a civil date (proleptic Gregorian)
+    fn from_days(days: i64) -> Self {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+        Self {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+        }
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = String;
+
+    fn try_from(date: String) -> Result<Self, Self::Error> {
+        Self::parse(&date).ok_or_else(|| format!("invalid date: {date}"))
+    }
+}
+
+impl From<Date> for String {
+    fn from(date: Date) -> Self {
+        date.to_string()
+    }
+}
+
+// The latest save of each collection; also serializes every write
+static SAVES: LazyLock<Mutex<HashMap<Name, u64>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// Saves are numbered when requested, so a stale snapshot can never
+// overwrite a newer one; even if its task happens to run last.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+// Any save requested before a collection was renamed, merged or deleted is discarded
+fn forget(saves: &mut HashMap<Name, u64>, name: Name) {
+    let _ = saves.insert(name, GENERATION.load(atomic::Ordering::Relaxed) + 1);
+}
+
+// Names are case insensitive, since they share a file otherwise
+async fn ensure_available(name: &Name) -> Result<(), anywho::Error> {
+    if fs::try_exists(collection_path(name)).await? {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("a collection named {name} already exists"),
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+// Written to a temporary file first and renamed into place, so a crash
+// can never leave a collection half written.
+async fn write(collection: &Collection) -> Result<(), anywho::Error> {
+    let path = collection_path(&collection.name);
+    let temporary = path.with_extension("ron.tmp");
+
+    fs::create_dir_all(collections_dir()).await?;
+
+    let mut file = fs::File::create(&temporary).await?;
+    file.write_all(
+        ron::ser::to_string_pretty(collection, ron::ser::PrettyConfig::default())?.as_bytes(),
+    )
+    .await?;
+    file.sync_all().await?;
+    drop(file);
+
+    fs::rename(temporary, path).await?;
+
+    // The rename itself is only durable once the directory is synced
+    #[cfg(unix)]
+    fs::File::open(collections_dir()).await?.sync_all().await?;
+
+    Ok(())
+}
+
+// Collections stored under an older file name are moved to their current
+// one; renamed if another collection only differing in case got there first
+async fn relocate(mut collection: Collection, path: &Path) -> Result<Collection, anywho::Error> {
+    collection.name = available(collection.name).await?;
+
+    write(&collection).await?;
+    fs::remove_file(path).await?;
+
+    Ok(collection)
+}
+
+// The name itself, or the first numbered variant of it that is not taken by
+// another collection. A file holding the very same name is ours, written by
+// an earlier relocation that was interrupted.
+async fn available(name: Name) -> Result<Name, anywho::Error> {
+    let mut candidate = name.clone();
+    let mut number = 1;
+
+    loop {
+        let path = collection_path(&candidate);
+
+        if !fs::try_exists(&path).await? {
+            break;
+        }
+
+        let occupant: Collection = ron::from_str(&fs::read_to_string(&path).await?)?;
+
+        if occupant.name == candidate {
+            break;
+        }
+
+        number += 1;
+        candidate = Name(format!("{name} ({number})"));
+    }
+
+    Ok(candidate)
+}
+
+// The name itself, or the first numbered variant of it that does not collide
+// with any of the given ones
+fn distinct(name: Name, taken: &[Name]) -> Name {
+    let mut candidate = name.clone();
+    let mut number = 1;
+
+    while taken.iter().any(|taken| taken.collides(&candidate)) {
+        number += 1;
+        candidate = Name(format!("{name} ({number})"));
+    }
+
+    candidate
+}
+
+// All collections used to be stored together in a single file
+async fn migrate() -> Result<(), anywho::Error> {
+    let legacy = data_dir().join("collections.ron");
+    let _saves = SAVES.lock().await;
+
+    if !fs::try_exists(&legacy).await? {
+        return Ok(());
+    }
+
+    let collections: Vec<Collection> = ron::from_str(&fs::read_to_string(&legacy).await?)?;
+    let mut names = Vec::with_capacity(collections.len());
+
+    // Files written by an interrupted migration are simply overwritten
+    for mut collection in collections {
+        collection.name = distinct(collection.name, &names);
+        names.push(collection.name.clone());
+
+        write(&collection).await?;
+    }
+
+    let total = names.len();
+
+    fs::rename(&legacy, legacy.with_extension("ron.bak")).await?;
+
+    log::info!("Migrated {total} collections");
+
+    Ok(())
+}
+
+fn collection_path(name: &Name) -> PathBuf {
+    collections_dir().join(format!("{stem}.ron", stem = file_stem(name)))
+}
+
+// A readable slug of the name, made unique by a hash of it. The name itself
+// is stored inside the file.
+fn file_stem(name: &Name) -> String {
+    const MAX_SLUG: usize = 32;
+
+    let key = name.key();
+    let mut slug = String::new();
+
+    for character in key.chars() {
+        if slug.len() >= MAX_SLUG {
+            break;
+        }
+
+        if character.is_ascii_alphanumeric() {
+            slug.push(character);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    // FNV-1a, which is stable across builds unlike `DefaultHasher`
+    let hash = key.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+
+    let slug = slug.trim_end_matches('-');
+
+    if slug.is_empty() {
+        format!("{hash:016x}")
+    } else {
+        format!("{slug}-{hash:016x}")
+    }
+}
+
+fn collections_dir() -> PathBuf {
+    data_dir().join("collections")
 }
 
 fn data_dir() -> PathBuf {
@@ -202,3 +1198,153 @@ fn data_dir() -> PathBuf {
         .unwrap_or_default()
         .join(env!("CARGO_PKG_NAME"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_exist_on_the_calendar() {
+        assert!(Date::parse("2024-02-29").is_some());
+        assert!(Date::parse("2000-02-29").is_some());
+        assert!(Date::parse("2025-12-31").is_some());
+
+        for invalid in [
+            "2023-02-29",
+            "1900-02-29",
+            "2025-04-31",
+            "2025-13-01",
+            "2025-01-00",
+        ] {
+            assert_eq!(Date::parse(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn recently_added_cards_come_first() {
+        let mut collection = collection();
+
+        let _ = collection.add(id("swsh1-1"), Variant::Normal);
+        let _ = collection.add(id("swsh1-2"), Variant::Normal);
+        let _ = collection.add(id("swsh1-3"), Variant::Normal);
+        let _ = collection.add(id("swsh1-1"), Variant::Reverse);
+        let _ = collection.set_quantity(id("swsh1-3"), Variant::Normal, 0);
+
+        let recent = |collection: &Collection| -> Vec<String> {
+            collection
+                .recently_added()
+                .iter()
+                .map(|card| card.as_str().to_owned())
+                .collect()
+        };
+
+        assert_eq!(recent(&collection), ["swsh1-1", "swsh1-2"]);
+
+        let _ = collection.add(id("swsh1-4"), Variant::Normal);
+
+        assert_eq!(recent(&collection), ["swsh1-4", "swsh1-1", "swsh1-2"]);
+    }
+
+    #[test]
+    fn every_change_is_kept() {
+        let mut collection = collection();
+
+        let _ = collection.add(id("swsh1-1"), Variant::Normal);
+        let _ = collection.add(id("swsh1-1"), Variant::Normal);
+        let _ = collection.remove(id("swsh1-1"), 1);
+
+        let kinds: Vec<_> = collection.events.iter().map(|event| event.kind).collect();
+
+        assert_eq!(
+            kinds,
+            [
+                event::Kind::Added { quantity: 1 },
+                event::Kind::QuantityChanged { from: 1, to: 2 },
+                event::Kind::QuantityChanged { from: 2, to: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn collector_is_the_author() {
+        let mut collection = collection();
+        collection.collector = Some("Ash".to_owned());
+
+        let _ = collection.add(id("swsh1-1"), Variant::Normal);
+
+        assert_eq!(collection.events[0].author.as_deref(), Some("Ash"));
+    }
+
+    #[test]
+    fn names_differing_in_case_share_a_file() {
+        let path = |name: &str| collection_path(&Name::parse(name).expect("Valid name"));
+
+        assert_eq!(path("Ash"), path("ash"));
+        assert_ne!(path("Ash"), path("Ash!"));
+        assert!(
+            Name::parse("ASH")
+                .expect("Valid name")
+                .collides(&Name::parse("ash").expect("Valid name"))
+        );
+    }
+
+    #[test]
+    fn legacy_names_are_made_distinct() {
+        let mut names = Vec::new();
+
+        for name in ["ash", "Ash", "ASH", "Misty"] {
+            let name = distinct(Name::parse(name).expect("Valid name"), &names);
+            names.push(name);
+        }
+
+        let names: Vec<_> = names.iter().map(Name::as_str).collect();
+
+        assert_eq!(names, ["ash", "Ash (2)", "ASH (3)", "Misty"]);
+    }
+
+    #[test]
+    fn file_names_are_short_slugs() {
+        let file = |name: &str| {
+            collection_path(&Name::parse(name).expect("Valid name"))
+                .file_name()
+                .and_then(std::ffi::OsStr::to_str)
+                .map(str::to_owned)
+                .expect("Valid file name")
+        };
+
+        assert!(file("Ash's Binder").starts_with("ash-s-binder-"));
+        assert_eq!(file("ポケモン").len(), "0000000000000000.ron".len());
+        assert!(file(&"Pikachu".repeat(100)).len() <= 32 + "-0000000000000000.ron".len());
+    }
+
+    #[test]
+    fn merged_events_keep_their_history() {
+        let mut target = collection();
+        let mut other = collection();
+
+        let _ = target.add(id("swsh1-1"), Variant::Normal);
+        let _ = other.add(id("swsh1-2"), Variant::Normal);
+
+        target.events[0].at = Timestamp(2);
+        other.events[0].at = Timestamp(1);
+
+        target.absorb(other);
+
+        let cards: Vec<_> = target
+            .events
+            .iter()
+            .map(|event| event.card.as_str())
+            .collect();
+
+        assert_eq!(cards, ["swsh1-2", "swsh1-1"]);
+        assert_eq!(target.total_cards(), 2);
+    }
+
+    fn collection() -> Collection {
+        Collection::new(Name::parse("Test").expect("Valid name"))
+    }
+
+    fn id(id: &str) -> card::Id {
+        ron::from_str(&format!("(\"{id}\")")).expect("Deserialize card id")
+    }
+}
@@ -0,0 +1,773 @@
+use crate::card::pricing;
+use crate::collection::{Collection, Variant};
+use crate::pokebase::card;
+use crate::pokebase::{Card, Database};
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+impl Format {
+    pub const ALL: &[Self] = &[Self::Csv, Self::Json];
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?;
+
+        if extension.eq_ignore_ascii_case("csv") {
+            Some(Self::Csv)
+        } else if extension.eq_ignore_ascii_case("json") {
+            Some(Self::Json)
+        } else {
+            None
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Csv => "CSV",
+            Self::Json => "JSON",
+        })
+    }
+}
+
+// A variant of a card in a collection, as exported
+#[derive(Debug, Clone, Serialize)]
+struct Row<'a> {
+    id: &'a str,
+    set: &'a str,
+    number: &'a str,
+    name: &'a str,
+    variant: Variant,
+    quantity: usize,
+    // In dollars, per copy
+    price: Option<f64>,
+}
+
+const HEADER: [&str; 7] = [
+    "id", "set", "number", "name", "variant", "quantity", "price",
+];
+
+// Exports the collection into a new file in the downloads directory and
+// returns it
+pub fn export(
+    collection: &Collection,
+    database: &Database,
+    prices: &pricing::Map,
+    rate: pricing::ExchangeRate,
+    format: Format,
+) -> impl Future<Output = Result<PathBuf, anywho::Error>> + 'static {
+    let content = serialize(collection, database, prices, rate, format);
+    let stem = file_name(collection.name.as_str());
+
+    async move {
+        let content = content?;
+
+        let directory = dirs::download_dir()
+            .or_else(dirs::document_dir)
+            .unwrap_or_default();
+
+        fs::create_dir_all(&directory).await?;
+
+        let extension = format.extension();
+        let mut path = directory.join(format!("{stem}.{extension}"));
+        let mut copy = 1;
+
+        // Earlier exports are kept
+        let mut file = loop {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .await
+            {
+                Ok(file) => break file,
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+                    copy += 1;
+                    path = directory.join(format!("{stem} ({copy}).{extension}"));
+                }
+                Err(error) => return Err(error.into()),
+            }
+        };
+
+        file.write_all(content.as_bytes()).await?;
+        file.sync_all().await?;
+
+        Ok(path)
+    }
+}
+
+// The name as typed, minus anything file systems reject
+fn file_name(name: &str) -> String {
+    const MAX_LENGTH: usize = 100;
+
+    let name: String = name
+        .chars()
+        .map(|character| {
+            if character.is_control() || r#"<>:"/\|?*"#.contains(character) {
+                '_'
+            } else {
+                character
+            }
+        })
+        .take(MAX_LENGTH)
+        .collect();
+
+    // Windows drops trailing dots and spaces
+    let name = name.trim().trim_end_matches('.').trim_end();
+
+    if name.is_empty() {
+        "Collection".to_owned()
+    } else {
+        name.to_owned()
+    }
+}
+
+fn serialize(
+    collection: &Collection,
+    database: &Database,
+    prices: &pricing::Map,
+    rate: pricing::ExchangeRate,
+    format: Format,
+) -> Result<String, anywho::Error> {
+    let rows: Vec<_> = collection
+        .cards
+        .iter()
+        .flat_map(|(id, amount)| {
+            let card = database.cards.get(id);
+            let pricing = prices.get(id).filter(|pricing| !pricing.missing);
+
+            Variant::ALL.iter().filter_map(move |&variant| {
+                let quantity = amount.count(variant);
+
+                if quantity == 0 {
+                    return None;
+                }
+
+                let price = pricing.map(|pricing| match variant {
+                    Variant::Normal => pricing.value_in_dollars(1, 0, rate),
+                    Variant::Reverse => pricing.value_in_dollars(0, 1, rate),
+                });
+
+                Some(Row {
+                    id: id.as_str(),
+                    set: card.map(|card| card.set.as_str()).unwrap_or_default(),
+                    number: id.number(),
+                    name: card.map(|card| card.name.as_str()).unwrap_or_default(),
+                    variant,
+                    quantity,
+                    price: price.map(pricing::Dollars::amount),
+                })
+            })
+        })
+        .collect();
+
+    match format {
+        Format::Json => Ok(serde_json::to_string_pretty(&rows)?),
+        Format::Csv => {
+            let mut csv = HEADER.join(",");
+            csv.push('\n');
+
+            for row in rows {
+                let fields = [
+                    row.id.to_owned(),
+                    row.set.to_owned(),
+                    row.number.to_owned(),
+                    row.name.to_owned(),
+                    format!("{:?}", row.variant),
+                    row.quantity.to_string(),
+                    row.price
+                        .map(|price| format!("{price:.2}"))
+                        .unwrap_or_default(),
+                ];
+
+                csv.push_str(&fields.map(|field| escape(&field)).join(","));
+                csv.push('\n');
+            }
+
+            Ok(csv)
+        }
+    }
+}
+
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+// The cards found in an imported file
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    pub matched: Vec<Match>,
+    pub ambiguous: Vec<(Line, Vec<card::Id>)>,
+    pub unmatched: Vec<Line>,
+}
+
+impl Report {
+    pub fn total_cards(&self) -> usize {
+        self.matched.iter().map(|entry| entry.quantity).sum()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Match {
+    pub card: card::Id,
+    pub variant: Variant,
+    pub quantity: usize,
+}
+
+// A row of an imported file that could not be resolved
+#[derive(Debug, Clone)]
+pub struct Line {
+    pub number: usize,
+    pub content: String,
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Row {}: {}", self.number, self.content)
+    }
+}
+
+// A row of an imported file, by lowercase column name
+#[derive(Debug, Clone)]
+pub struct Record {
+    line: usize,
+    fields: BTreeMap<String, String>,
+    // The raw row, when it could not be split into fields
+    malformed: Option<String>,
+}
+
+impl Record {
+    // Other trackers name their columns differently
+    fn get(&self, aliases: &[&str]) -> Option<&str> {
+        aliases
+            .iter()
+            .find_map(|alias| self.fields.get(*alias))
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    }
+
+    fn id(&self) -> Option<&str> {
+        self.get(&["id", "card id", "card_id", "cardid"])
+    }
+
+    fn set(&self) -> Option<&str> {
+        self.get(&[
+            "set",
+            "set id",
+            "set_id",
+            "set code",
+            "set_code",
+            "set name",
+            "set_name",
+            "edition",
+            "expansion",
+        ])
+    }
+
+    fn number(&self) -> Option<&str> {
+        self.get(&[
+            "number",
+            "card number",
+            "card_number",
+            "collector number",
+            "collector_number",
+            "no",
+            "#",
+        ])
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.get(&["name", "card name", "card_name", "card"])
+    }
+
+    fn variant(&self) -> Variant {
+        let variant = self
+            .get(&["variant", "finish", "printing", "foil"])
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        if variant.contains("reverse") {
+            Variant::Reverse
+        } else {
+            Variant::Normal
+        }
+    }
+
+    // Anything but a whole number of copies is a mistake we should report
+    fn quantity(&self) -> Option<usize> {
+        const MAX: usize = 10_000;
+
+        let Some(quantity) = self.get(&["quantity", "qty", "count", "amount", "copies"]) else {
+            return Some(1);
+        };
+
+        let quantity = quantity.parse::<usize>().ok().or_else(|| {
+            // Spreadsheets may write whole numbers as decimals; e.g. "2.0"
+            let (whole, fraction) = quantity.split_once('.')?;
+
+            if fraction.bytes().all(|digit| digit == b'0') {
+                whole.parse().ok()
+            } else {
+                None
+            }
+        })?;
+
+        (quantity <= MAX).then_some(quantity)
+    }
+
+    fn to_line(&self) -> Line {
+        if let Some(raw) = &self.malformed {
+            return Line {
+                number: self.line,
+                content: raw.trim().to_owned(),
+            };
+        }
+
+        let content = [self.id(), self.set(), self.number(), self.name()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" · ");
+
+        Line {
+            number: self.line,
+            content,
+        }
+    }
+}
+
+pub async fn read(path: PathBuf) -> Result<Vec<Record>, anywho::Error> {
+    let content = fs::read_to_string(&path).await?;
+
+    match Format::from_path(&path) {
+        Some(Format::Json) => parse_json(&content),
+        Some(Format::Csv) | None => Ok(parse_csv(&content)),
+    }
+}
+
+fn parse_json(content: &str) -> Result<Vec<Record>, anywho::Error> {
+    use serde_json::Value;
+
+    let rows: Vec<BTreeMap<String, Value>> = serde_json::from_str(content)?;
+
+    Ok(rows
+        .into_iter()
+        .enumerate()
+        .map(|(index, row)| Record {
+            line: index + 1,
+            fields: row
+                .into_iter()
+                .filter_map(|(key, value)| {
+                    let value = match value {
+                        Value::String(value) => value,
+                        Value::Number(number) => number.to_string(),
+                        Value::Bool(value) => value.to_string(),
+                        _ => return None,
+                    };
+
+                    Some((key.to_lowercase(), value))
+                })
+                .collect(),
+            malformed: None,
+        })
+        .collect())
+}
+
+fn parse_csv(content: &str) -> Vec<Record> {
+    // Some spreadsheets use semicolons, depending on the locale
+    let delimiter = match content.lines().find(|line| !line.trim().is_empty()) {
+        Some(header) if header.contains(';') && !header.contains(',') => ';',
+        _ => ',',
+    };
+
+    let mut records = records(content, delimiter).into_iter();
+
+    let Some((_, header)) = records.next() else {
+        return Vec::new();
+    };
+
+    let columns: Vec<String> = split(header, delimiter)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|column| column.trim().trim_start_matches('\u{feff}').to_lowercase())
+        .collect();
+
+    records
+        .map(|(line, record)| match split(record, delimiter) {
+            Some(fields) => Record {
+                line,
+                fields: columns.iter().cloned().zip(fields).collect(),
+                malformed: None,
+            },
+            None => Record {
+                line,
+                fields: BTreeMap::new(),
+                malformed: Some(record.to_owned()),
+            },
+        })
+        .collect()
+}
+
+// The non-blank records of a CSV file with the line each starts on; quoted
+// fields may span several lines
+fn records(content: &str, delimiter: char) -> Vec<(usize, &str)> {
+    let mut records = Vec::new();
+    let mut start = (0, 1);
+    let mut line = 1;
+    let mut is_quoted = false;
+    let mut is_field_start = true;
+    let mut chars = content.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' if is_quoted => {
+                if chars.next_if(|(_, next)| *next == '"').is_none() {
+                    is_quoted = false;
+                }
+            }
+            // A stray quote is left for `split` to report
+            '"' => is_quoted = is_field_start,
+            '\n' => {
+                line += 1;
+
+                if !is_quoted {
+                    records.push((start.1, &content[start.0..index]));
+                    start = (index + 1, line);
+                }
+            }
+            _ => {}
+        }
+
+        is_field_start = !is_quoted && (c == delimiter || c == '\n');
+    }
+
+    records.push((start.1, &content[start.0..]));
+
+    records
+        .into_iter()
+        .map(|(line, record)| (line, record.strip_suffix('\r').unwrap_or(record)))
+        .filter(|(_, record)| !record.trim().is_empty())
+        .collect()
+}
+
+// Quotes only open a field at its start; anywhere else they make the record
+// malformed
+fn split(record: &str, delimiter: char) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut is_quoted = false;
+    let mut is_field_start = true;
+    let mut chars = record.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if is_quoted => {
+                if chars.next_if_eq(&'"').is_some() {
+                    field.push('"');
+                } else {
+                    is_quoted = false;
+                }
+            }
+            '"' if is_field_start => is_quoted = true,
+            '"' => return None,
+            c if c == delimiter && !is_quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+
+        is_field_start = !is_quoted && c == delimiter;
+    }
+
+    if is_quoted {
+        return None;
+    }
+
+    fields.push(field);
+    Some(fields)
+}
+
+// Rows are matched by card id first, and by set and number otherwise
+pub fn resolve(records: &[Record], database: &Database) -> Report {
+    let by_id: HashMap<&str, &Card> = database
+        .cards
+        .values()
+        .iter()
+        .map(|card| (card.id.as_str(), card))
+        .collect();
+
+    // Cards by set (id or any of its names) and number, both folded
+    let mut by_number: HashMap<(String, String), Vec<&Card>> = HashMap::new();
+
+    for card in database.cards.values() {
+        let number = fold_number(card.id.number());
+        let names = database
+            .sets
+            .get(&card.set)
+            .into_iter()
+            .flat_map(|set| set.name.values());
+
+        let sets: HashSet<String> = std::iter::once(card.set.as_str())
+            .chain(names.map(String::as_str))
+            .map(str::to_lowercase)
+            .collect();
+
+        for set in sets {
+            by_number
+                .entry((set, number.clone()))
+                .or_default()
+                .push(card);
+        }
+    }
+
+    let mut report = Report::default();
+
+    for record in records {
+        if record.malformed.is_some() {
+            report.unmatched.push(record.to_line());
+            continue;
+        }
+
+        let Some(quantity) = record.quantity().filter(|quantity| *quantity > 0) else {
+            report.unmatched.push(record.to_line());
+            continue;
+        };
+
+        let candidates: Vec<&Card> = match (record.id(), record.set(), record.number()) {
+            (Some(id), _, _) if by_id.contains_key(id) => vec![by_id[id]],
+            (_, Some(set), Some(number)) => {
+                let candidates = by_number
+                    .get(&(set.to_lowercase(), fold_number(number)))
+                    .cloned()
+                    .unwrap_or_default();
+
+                // The name may settle a tie
+                match record.name() {
+                    Some(name) if candidates.len() > 1 => {
+                        let named: Vec<_> = candidates
+                            .iter()
+                            .copied()
+                            .filter(|card| card.name.contains(&name.to_lowercase()))
+                            .collect();
+
+                        if named.is_empty() { candidates } else { named }
+                    }
+                    _ => candidates,
+                }
+            }
+            _ => Vec::new(),
+        };
+
+        match candidates.as_slice() {
+            [] => report.unmatched.push(record.to_line()),
+            [card] => report.matched.push(Match {
+                card: card.id.clone(),
+                variant: record.variant(),
+                quantity,
+            }),
+            _ => report.ambiguous.push((
+                record.to_line(),
+                candidates.iter().map(|card| card.id.clone()).collect(),
+            )),
+        }
+    }
+
+    report
+}
+
+fn fold_number(number: &str) -> String {
+    number.trim_start_matches('0').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::pokebase::Set;
+
+    #[test]
+    fn exports_are_named_after_the_collection() {
+        assert_eq!(file_name("Ash's Binder"), "Ash's Binder");
+        assert_eq!(file_name("Kanto/Johto: 1*2?"), "Kanto_Johto_ 1_2_");
+        assert_eq!(file_name("Trainer..."), "Trainer");
+        assert_eq!(file_name(" ... "), "Collection");
+    }
+
+    #[test]
+    fn fields_are_split_by_delimiter_outside_of_quotes() {
+        let split = |record, delimiter| split(record, delimiter).expect("Split record");
+
+        assert_eq!(split("a,b,,c", ','), ["a", "b", "", "c"]);
+        assert_eq!(split("a;\"b;c\";d", ';'), ["a", "b;c", "d"]);
+        assert_eq!(split("\"say \"\"hi\"\"\",x", ','), ["say \"hi\"", "x"]);
+        assert_eq!(split("\"two\nlines\"", ','), ["two\nlines"]);
+        assert_eq!(split("\"\",x", ','), ["", "x"]);
+    }
+
+    #[test]
+    fn stray_quotes_are_malformed() {
+        assert_eq!(split("Farfetch\"d,1", ','), None);
+        assert_eq!(split("a,\"b\"c\"", ','), None);
+        assert_eq!(split("\"unterminated,1", ','), None);
+    }
+
+    #[test]
+    fn stray_quotes_do_not_swallow_lines() {
+        let records = parse_csv(
+            "name,number\n\
+             Farfetch\"d,1\n\
+             Celebi,2\n",
+        );
+
+        let lines: Vec<_> = records.iter().map(|record| record.line).collect();
+
+        assert_eq!(lines, [2, 3]);
+        assert_eq!(records[0].malformed.as_deref(), Some("Farfetch\"d,1"));
+        assert_eq!(records[1].name(), Some("Celebi"));
+    }
+
+    #[test]
+    fn csv_records_keep_their_physical_lines() {
+        let records = parse_csv(
+            "\u{feff}ID,Name,Qty\r\n\
+             \r\n\
+             swsh1-1,\"Celebi\r\nV\",2\r\n\
+             swsh1-2,Other,1\r\n",
+        );
+
+        let lines: Vec<_> = records.iter().map(|record| record.line).collect();
+
+        assert_eq!(lines, [3, 5]);
+        assert_eq!(records[0].id(), Some("swsh1-1"));
+        assert_eq!(records[0].name(), Some("Celebi\r\nV"));
+        assert_eq!(records[0].quantity(), Some(2));
+    }
+
+    #[test]
+    fn csv_with_semicolons_is_parsed() {
+        let records = parse_csv("set;number;quantity\nswsh1;001;3\n");
+
+        assert_eq!(records[0].set(), Some("swsh1"));
+        assert_eq!(records[0].number(), Some("001"));
+        assert_eq!(records[0].quantity(), Some(3));
+    }
+
+    #[test]
+    fn quantities_are_whole_and_sane() {
+        let quantity = |quantity: &str| record(&[("quantity", quantity)]).quantity();
+
+        assert_eq!(record(&[]).quantity(), Some(1));
+        assert_eq!(quantity("2"), Some(2));
+        assert_eq!(quantity("2.0"), Some(2));
+
+        for invalid in ["2.5", "inf", "NaN", "1e20", "-3", "99999999999"] {
+            assert_eq!(quantity(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn records_are_resolved() {
+        let database = database();
+        let mut records = [
+            record(&[("id", "swsh1-1"), ("quantity", "2")]),
+            record(&[
+                ("set", "Sword & Shield"),
+                ("number", "1"),
+                ("finish", "Reverse Holo"),
+            ]),
+            record(&[("set", "swsh1"), ("number", "2")]),
+            record(&[("set", "swsh1"), ("number", "999")]),
+            record(&[("id", "swsh1-1"), ("quantity", "inf")]),
+            record(&[("id", "swsh1-1"), ("quantity", "0")]),
+        ];
+
+        for (index, record) in records.iter_mut().enumerate() {
+            record.line = index + 1;
+        }
+
+        let report = resolve(&records, &database);
+
+        let matched: Vec<_> = report
+            .matched
+            .iter()
+            .map(|entry| (entry.card.as_str(), entry.variant, entry.quantity))
+            .collect();
+
+        assert_eq!(
+            matched,
+            [
+                ("swsh1-1", Variant::Normal, 2),
+                ("swsh1-1", Variant::Reverse, 1)
+            ]
+        );
+        assert_eq!(report.ambiguous.len(), 1);
+        assert_eq!(report.ambiguous[0].1.len(), 2);
+
+        let unmatched: Vec<_> = report.unmatched.iter().map(|line| line.number).collect();
+
+        assert_eq!(unmatched, [4, 5, 6]);
+    }
+
+    fn record(fields: &[(&str, &str)]) -> Record {
+        Record {
+            line: 0,
+            fields: fields
+                .iter()
+                .map(|(key, value)| ((*key).to_owned(), (*value).to_owned()))
+                .collect(),
+            malformed: None,
+        }
+    }
+
+    fn database() -> Database {
+        let set: Set = ron::from_str(
+            r#"(
+                id: ("swsh1"),
+                name: {"en": "Sword & Shield"},
+                series: ("swsh"),
+                release_date: "2020-02-07",
+                total_cards: 216,
+            )"#,
+        )
+        .expect("Deserialize set");
+
+        let cards: [Card; 3] = ["swsh1-1", "swsh1-2", "swsh1-002"].map(|id| {
+            ron::from_str(&format!(
+                r#"(
+                    id: ("{id}"),
+                    set: ("swsh1"),
+                    name: {{"en": "Celebi V"}},
+                    types: [Grass],
+                    rarity: HoloRareV,
+                    variants: (first_edition: false, holo: true, normal: false, reverse: false, w_promo: false),
+                    illustrator: None,
+                    pokedex: [251],
+                )"#
+            ))
+            .expect("Deserialize card")
+        });
+
+        Database::new(Vec::new(), Vec::new(), vec![set], cards.into())
+    }
+}
@@ -10,6 +10,7 @@ mod widget;
 use crate::binder::Binder;
 use crate::card::pricing::{self, Pricing};
 use crate::collection::Collection;
+use crate::pokebase::session;
 use crate::pokebase::{Database, Result, Session};
 use crate::screen::Screen;
 use crate::screen::binders;
@@ -18,6 +19,7 @@ use crate::widget::logo;
 
 use iced::time::Instant;
 use iced::widget::{button, center, column, container, row, text};
+use iced::window;
 use iced::{Center, Element, Fill, Font, Subscription, Task, Theme};
 
 use std::env;
@@ -35,6 +37,7 @@ pub fn main() -> iced::Result {
     .font(icon::FONT)
     .default_font(Font::MONOSPACE)
     .window_size((1700.0, 950.0))
+    .exit_on_close_request(false)
     .run()
 }
 
@@ -49,6 +52,7 @@ enum State {
         database: Database,
         session: Session,
         screen: Screen,
+        images: card::Store,
         prices: pricing::Map,
         rate: pricing::ExchangeRate,
     },
@@ -56,12 +60,13 @@ enum State {
 
 #[derive(Debug, Clone)]
 enum Message {
-    Loaded(Result<(Database, pricing::Map, pricing::ExchangeRate), anywho::Error>),
+    Loaded(Result<(Database, Session, pricing::Map, pricing::ExchangeRate), anywho::Error>),
     Welcome(welcome::Message),
     Binders(binders::Message),
     OpenBinders,
     Browse,
     PricingUpdated((card::Id, Pricing)),
+    CloseRequested(window::Id),
 }
 
 impl Holodeck {
@@ -74,10 +79,18 @@ impl Holodeck {
             Task::perform(
                 async {
                     let database = Database::load().await?;
+                    let session = Session::with_config(session::Config {
+                        pokemon_tcg_api_key: env::var("POKEMONTCG_API_KEY").ok(),
+                        quotas: dirs::data_dir()
+                            .map(|data| data.join(env!("CARGO_PKG_NAME")).join("quotas")),
+                        ..session::Config::default()
+                    });
                     let prices = Pricing::list().await?;
-                    let rate = pricing::ExchangeRate::fetch().await.unwrap_or_default();
+                    let rate = pricing::ExchangeRate::fetch(&session)
+                        .await
+                        .unwrap_or_default();
 
-                    Ok((database, prices, rate))
+                    Ok((database, session, prices, rate))
                 },
                 Message::Loaded,
             ),
@@ -88,11 +101,9 @@ impl Holodeck {
         self.now = now;
 
         match message {
-            Message::Loaded(Ok((database, prices, rate))) => {
+            Message::Loaded(Ok((database, session, prices, rate))) => {
                 let (welcome, task) = screen::Welcome::new();
 
-                let session = Session::new(env::var("POKEMONTCG_API_KEY").ok()); // TODO: Configuration
-
                 let price_updates = Task::run(
                     Pricing::subscribe(&database, &session),
                     Message::PricingUpdated,
@@ -102,6 +113,7 @@ impl Holodeck {
                     database,
                     session,
                     screen: Screen::Welcome(welcome),
+                    images: card::Store::new(),
                     prices,
                     rate,
                 };
@@ -112,6 +124,7 @@ impl Holodeck {
                 let State::Ready {
                     screen,
                     database,
+                    images,
                     prices,
                     session,
                     ..
@@ -124,7 +137,7 @@ impl Holodeck {
                     return Task::none();
                 };
 
-                match welcome.update(message, database, prices, session, self.now) {
+                match welcome.update(message, database, images, prices, session, self.now) {
                     welcome::Action::None => Task::none(),
                     welcome::Action::Run(task) => task.map(Message::Welcome),
                     welcome::Action::Select(collection) => {
@@ -142,7 +155,9 @@ impl Holodeck {
             Message::Binders(message) => {
                 let State::Ready {
                     database,
+                    images,
                     prices,
+                    rate,
                     session,
                     screen:
                         Screen::Collecting {
@@ -156,7 +171,9 @@ impl Holodeck {
                 };
 
                 binders
-                    .update(message, collection, database, prices, session, self.now)
+                    .update(
+                        message, collection, database, images, prices, *rate, session, self.now,
+                    )
                     .map(Message::Binders)
             }
             Message::OpenBinders => {
@@ -168,6 +185,11 @@ impl Holodeck {
                     return Task::none();
                 };
 
+                // A new screen would drop any pending changes
+                if matches!(screen, screen::Collecting::Binders(_)) {
+                    return Task::none();
+                }
+
                 let binders = screen::Binders::new();
                 *screen = screen::Collecting::Binders(binders);
 
@@ -186,6 +208,21 @@ impl Holodeck {
 
                 Task::none()
             }
+            Message::CloseRequested(window) => {
+                let flush = match &mut self.state {
+                    State::Ready {
+                        screen:
+                            Screen::Collecting {
+                                collection,
+                                screen: screen::Collecting::Binders(binders),
+                            },
+                        ..
+                    } => binders.flush(collection).map(Message::Binders),
+                    _ => Task::none(),
+                };
+
+                flush.chain(window::close(window))
+            }
             Message::Loaded(Err(error)) => {
                 log::error!("{error}");
 
@@ -200,12 +237,13 @@ impl Holodeck {
             State::Ready {
                 database,
                 screen,
+                images,
                 prices,
                 rate,
                 ..
             } => match screen {
                 Screen::Welcome(welcome) => welcome
-                    .view(database, prices, *rate, self.now)
+                    .view(database, images, prices, *rate, self.now)
                     .map(Message::Welcome),
                 Screen::Collecting { collection, screen } => {
                     let tabs = [
@@ -253,7 +291,7 @@ impl Holodeck {
 
                     let screen = match screen {
                         screen::Collecting::Binders(binders) => binders
-                            .view(collection, database, prices, self.now)
+                            .view(collection, database, images, prices, *rate, self.now)
                             .map(Message::Binders),
                     };
 
@@ -264,18 +302,22 @@ impl Holodeck {
     }
 
     fn subscription(&self) -> Subscription<Message> {
+        let close_requests = window::close_requests().map(Message::CloseRequested);
+
         let State::Ready { screen, .. } = &self.state else {
-            return Subscription::none();
+            return close_requests;
         };
 
-        match screen {
+        let screen = match screen {
             Screen::Welcome(welcome) => welcome.subscription(self.now).map(Message::Welcome),
             Screen::Collecting { screen, .. } => match screen {
                 screen::Collecting::Binders(binders) => {
                     binders.subscription(self.now).map(Message::Binders)
                 }
             },
-        }
+        };
+
+        Subscription::batch([screen, close_requests])
     }
 
     fn theme(&self) -> Theme {
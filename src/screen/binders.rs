@@ -1,14 +1,15 @@
 use crate::binder;
 use crate::card;
 use crate::card::pricing;
-use crate::collection;
+use crate::collection::{self, exchange};
 use crate::icon;
 use crate::pokebase::{Card, Database, Session};
-use crate::widget::pokeball;
+use crate::widget::{card_face, pokeball};
 use crate::{Binder, Collection};
 
 use iced::animation;
 use iced::border;
+use iced::event::{self, Event};
 use iced::keyboard;
 use iced::padding;
 use iced::task;
@@ -27,6 +28,7 @@ use iced_palace::widget::typewriter;
 
 use function::Binary;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use tokio::time;
 
 pub struct Binders {
@@ -35,12 +37,23 @@ pub struct Binders {
     mode: binder::Mode,
     state: State,
     images: HashMap<card::Id, Image>,
+    downloads: HashMap<card::Id, (Source, task::Handle)>,
     animations: HashMap<card::Id, AnimationSet>,
+    hovered: Option<(card::Id, Source)>,
+    history: collection::History,
+    notice: Option<String>,
+    photos: HashMap<PathBuf, image::Handle>,
+    detaching: Option<card::Id>,
+    replacing: Option<card::Id>,
+    // The card under the cursor when a drag entered the window
+    drop_target: Option<(card::Id, Source)>,
+    saving: Option<task::Handle>,
 }
 
+// Loaded images live in the shared `card::Store`
 enum Image {
     Loading,
-    Loaded(image::Handle),
+    Missing,
     Errored,
 }
 
@@ -54,11 +67,23 @@ enum State {
         search_task: Option<task::Handle>,
         price_task: Option<task::Handle>,
     },
+    Showing {
+        card: card::Id,
+        selected: usize,
+        grade: String,
+        acquired: String,
+        max_price: String,
+    },
+    Importing {
+        report: exchange::Report,
+    },
+    Wishlist,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     ModeSelected(binder::Mode),
+    LanguageSelected(card::Language),
     PreviousPage,
     NextPage,
     Add(collection::Variant),
@@ -67,19 +92,62 @@ pub enum Message {
     SearchFinished(card::Search),
     Close,
     CardShown(card::Id, Source),
+    CardHidden(card::Id, Source),
     CardHovered(card::Id, Source, bool),
     ShowCard(card::Id),
+    CopySelected(usize),
+    CopyEdited(Edit),
+    RemoveCopy,
+    QuantityChanged(collection::Variant, usize),
+    ToggleWanted,
+    MaxPriceChanged(String),
+    CollectorChanged(String),
+    ShowWishlist,
+    Undo,
+    Redo,
     AddCard(card::Id),
-    ImageFetched(card::Id, Result<card::Image, anywho::Error>),
+    ImageFetched(
+        card::Id,
+        card::Resolution,
+        Result<Option<card::Image>, anywho::Error>,
+    ),
+    ImageAttached(card::Id, Source, Result<(), anywho::Error>),
+    ImageDetached(card::Id, Source, Result<bool, anywho::Error>),
+    PhotoAttached(card::Id, usize, Result<PathBuf, anywho::Error>),
+    PhotoLoaded(PathBuf, Result<card::Image, anywho::Error>),
+    RemovePhoto,
     PriceFetched(card::Id, Result<card::Pricing, anywho::Error>),
     CollectionSaved(Result<(), anywho::Error>),
-    TabPressed { shift: bool },
+    TabPressed {
+        shift: bool,
+    },
     EscapePressed,
     EnterPressed,
+    DeletePressed,
+    PlusPressed,
+    MinusPressed,
+    FileHovered,
+    FilesHoveredLeft,
+    FileDropped(PathBuf),
+    Export(exchange::Format),
+    Exported(Result<PathBuf, anywho::Error>),
+    FileRead(Result<Vec<exchange::Record>, anywho::Error>),
+    Import,
     Tick,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
+pub enum Edit {
+    Variant(collection::Variant),
+    Condition(collection::Condition),
+    Language(card::Language),
+    Company(collection::Company),
+    Grade(String),
+    Acquired(String),
+    Notes(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Source {
     Binder,
     Search,
@@ -102,7 +170,16 @@ impl Binders {
             mode: binder::Mode::GottaCatchEmAll,
             state: State::Idle,
             images: HashMap::new(),
+            downloads: HashMap::new(),
             animations: HashMap::new(),
+            hovered: None,
+            history: collection::History::new(),
+            notice: None,
+            photos: HashMap::new(),
+            detaching: None,
+            replacing: None,
+            drop_target: None,
+            saving: None,
         }
     }
 
@@ -111,16 +188,62 @@ impl Binders {
         message: Message,
         collection: &mut Collection,
         database: &Database,
+        images: &mut card::Store,
         prices: &mut pricing::Map,
+        rate: pricing::ExchangeRate,
         session: &Session,
         now: Instant,
     ) -> Task<Message> {
         match message {
             Message::ModeSelected(mode) => {
+                if self.mode == mode {
+                    return Task::none();
+                }
+
                 self.mode = mode;
+                self.spread = binder::Spread::default();
+                self.animations.clear();
+                self.cancel_downloads(Source::Binder);
 
                 Task::none()
             }
+            Message::LanguageSelected(language) => {
+                if collection.language == Some(language) {
+                    return Task::none();
+                }
+
+                collection.language = Some(language);
+
+                // Images in the previous language stay in the store, in case we switch back
+                self.images.clear();
+                self.animations.clear();
+                self.cancel_downloads(Source::Binder);
+
+                // Cards in sight are fetched again in the new language, unless seen before
+                let visible = self.visible_cards(collection, database);
+                let mut tasks = Vec::with_capacity(visible.len() + 1);
+
+                for card in visible {
+                    if images.contains(&card.id, collection.language(&card.id)) {
+                        continue;
+                    }
+
+                    let _ = self.images.insert(card.id.clone(), Image::Loading);
+
+                    tasks.push(self.download(
+                        card,
+                        card::Resolution::Low,
+                        Source::Binder,
+                        collection,
+                        database,
+                        session,
+                    ));
+                }
+
+                tasks.push(Task::perform(collection.save(), Message::CollectionSaved).discard());
+
+                Task::batch(tasks)
+            }
             Message::PreviousPage => {
                 let State::Idle = self.state else {
                     return Task::none();
@@ -129,6 +252,7 @@ impl Binders {
                 if let Some(spread) = self.spread.decrement() {
                     self.spread = spread;
                     self.animations.clear();
+                    self.cancel_downloads(Source::Binder);
                 }
 
                 Task::none()
@@ -138,7 +262,9 @@ impl Binders {
                     return Task::none();
                 };
 
-                let total_pages = self.binders.total_pages(self.mode.total_cards(database));
+                let total_pages = self
+                    .binders
+                    .total_pages(self.mode.total_cards(collection, database));
 
                 let new_spread = self
                     .spread
@@ -148,14 +274,15 @@ impl Binders {
                 if new_spread != self.spread {
                     self.spread = new_spread;
                     self.animations.clear();
+                    self.cancel_downloads(Source::Binder);
                 }
 
                 Task::none()
             }
             Message::Add(variant) => {
-                if let State::Adding { .. } = &self.state {
+                let State::Idle = self.state else {
                     return Task::none();
-                }
+                };
 
                 let (search_cards, handle) =
                     Task::perform(card::search("", database), Message::SearchFinished).abortable();
@@ -226,6 +353,7 @@ impl Binders {
             }
             Message::Close => {
                 self.state = State::Idle;
+                self.cancel_downloads(Source::Search);
 
                 Task::none()
             }
@@ -234,7 +362,7 @@ impl Binders {
                     return Task::none();
                 };
 
-                if self.images.contains_key(&card.id) {
+                if images.contains(&card.id, collection.language(&card.id)) {
                     match source {
                         Source::Binder => {
                             self.animations
@@ -250,70 +378,277 @@ impl Binders {
                     return Task::none();
                 }
 
+                if self.images.contains_key(&card.id) {
+                    return Task::none();
+                }
+
                 let _ = self.images.insert(card.id.clone(), Image::Loading);
 
-                Task::perform(
-                    card::Image::fetch(card, database, session),
-                    Message::ImageFetched.with(card.id.clone()),
+                self.download(
+                    card,
+                    card::Resolution::Low,
+                    source,
+                    collection,
+                    database,
+                    session,
                 )
             }
-            Message::CardHovered(card, source, hovered) => match source {
-                Source::Binder => {
-                    if let Some(animations) = self.animations.get_mut(&card) {
-                        animations.zoom.go_mut(hovered, now);
-                    }
-                    Task::none()
+            Message::CardHidden(card, source) => {
+                if self
+                    .downloads
+                    .get(&card)
+                    .is_some_and(|(download, _)| *download == source)
+                {
+                    self.cancel_download(&card);
                 }
-                Source::Search => {
-                    let State::Adding {
-                        animations,
-                        price_task,
-                        ..
-                    } = &mut self.state
-                    else {
-                        return Task::none();
-                    };
 
-                    let Some(card) = database.cards.get(&card) else {
-                        return Task::none();
-                    };
+                Task::none()
+            }
+            Message::CardHovered(card, source, hovered) => {
+                if self.detaching.as_ref() != Some(&card) {
+                    self.detaching = None;
+                }
+
+                if hovered {
+                    self.hovered = Some((card.clone(), source));
+                } else if self
+                    .hovered
+                    .as_ref()
+                    .is_some_and(|(current, _)| *current == card)
+                {
+                    self.hovered = None;
+                }
 
-                    for animation in animations.values_mut() {
-                        animation.zoom.go_mut(false, now);
+                // Zoomed cards deserve a sharper image
+                let is_low_resolution = images
+                    .best(&card, collection.language(&card))
+                    .map(|(_, resolution)| resolution)
+                    == Some(card::Resolution::Low);
+
+                let upgrade = match database.cards.get(&card) {
+                    Some(card)
+                        if hovered
+                            && is_low_resolution
+                            && !self.downloads.contains_key(&card.id) =>
+                    {
+                        self.download(
+                            card,
+                            card::Resolution::High,
+                            source,
+                            collection,
+                            database,
+                            session,
+                        )
                     }
+                    _ => Task::none(),
+                };
 
-                    if let Some(animations) = animations.get_mut(&card.id) {
-                        animations.zoom.go_mut(hovered, now);
+                let task = match source {
+                    Source::Binder => {
+                        if let Some(animations) = self.animations.get_mut(&card) {
+                            animations.zoom.go_mut(hovered, now);
+                        }
+                        Task::none()
                     }
+                    Source::Search => {
+                        let State::Adding {
+                            animations,
+                            price_task,
+                            ..
+                        } = &mut self.state
+                        else {
+                            return upgrade;
+                        };
 
-                    if !hovered || prices.contains(&card.id) {
-                        *price_task = None;
-                        return Task::none();
+                        let Some(card) = database.cards.get(&card) else {
+                            return upgrade;
+                        };
+
+                        for animation in animations.values_mut() {
+                            animation.zoom.go_mut(false, now);
+                        }
+
+                        if let Some(animations) = animations.get_mut(&card.id) {
+                            animations.zoom.go_mut(hovered, now);
+                        }
+
+                        if !hovered || prices.contains(&card.id) {
+                            *price_task = None;
+                            return upgrade;
+                        }
+
+                        let (task, handle) = Task::perform(
+                            {
+                                let fetch_price = card::Pricing::fetch(card, session);
+
+                                async move {
+                                    time::sleep(milliseconds(500)).await;
+                                    fetch_price.await
+                                }
+                            },
+                            Message::PriceFetched.with(card.id.clone()),
+                        )
+                        .abortable();
+
+                        *price_task = Some(handle.abort_on_drop());
+                        task
                     }
+                };
+
+                Task::batch([upgrade, task])
+            }
+            Message::ShowCard(card) => {
+                self.state = State::Idle;
+                self.select_copy(card, 0, collection);
+
+                self.load_photo(collection, database)
+            }
+            Message::CopySelected(index) => {
+                let State::Showing { card, .. } = &self.state else {
+                    return Task::none();
+                };
+
+                self.select_copy(card.clone(), index, collection);
+
+                self.load_photo(collection, database)
+            }
+            Message::CopyEdited(edit) => {
+                let State::Showing {
+                    card,
+                    selected,
+                    grade,
+                    acquired,
+                    ..
+                } = &mut self.state
+                else {
+                    return Task::none();
+                };
+
+                let card = card.clone();
+                let selected = *selected;
+                let language = collection.language(&card);
+                let is_typing = matches!(edit, Edit::Grade(_) | Edit::Acquired(_) | Edit::Notes(_));
 
-                    let (task, handle) = Task::perform(
-                        {
-                            let fetch_price = card::Pricing::fetch(card, session);
+                let change = collection.edit(card.clone(), |amount| {
+                    let Some(copy) = amount.copies.get_mut(selected) else {
+                        return;
+                    };
 
-                            async move {
-                                time::sleep(milliseconds(500)).await;
-                                fetch_price.await
+                    match edit {
+                        Edit::Variant(variant) => copy.variant = variant,
+                        Edit::Condition(condition) => copy.condition = condition,
+                        Edit::Language(language) => copy.language = Some(language),
+                        Edit::Company(company) => copy.company = Some(company),
+                        Edit::Grade(new_grade) => {
+                            // Half-typed grades are kept until they make sense
+                            if new_grade.trim().is_empty() {
+                                copy.grade = None;
+                            } else if let Some(parsed) = collection::Grade::parse(&new_grade) {
+                                copy.grade = Some(parsed);
                             }
-                        },
-                        Message::PriceFetched.with(card.id.clone()),
-                    )
-                    .abortable();
 
-                    *price_task = Some(handle.abort_on_drop());
-                    task
+                            *grade = new_grade;
+                        }
+                        Edit::Acquired(new_date) => {
+                            if new_date.trim().is_empty() {
+                                copy.acquired = None;
+                            } else if let Some(date) = collection::Date::parse(&new_date) {
+                                copy.acquired = Some(date);
+                            }
+
+                            *acquired = new_date;
+                        }
+                        Edit::Notes(notes) => copy.notes = notes,
+                    }
+                });
+
+                if is_typing {
+                    self.history.amend(change);
+
+                    return self.save_later(collection);
                 }
-            },
-            Message::ShowCard(card) => {
-                // TODO
-                dbg!(card);
+
+                self.history.push(change);
+
+                self.refresh(&card, language, collection, database, images, session)
+            }
+            Message::RemoveCopy => {
+                let State::Showing { card, selected, .. } = &self.state else {
+                    return Task::none();
+                };
+
+                let card = card.clone();
+                let language = collection.language(&card);
+
+                self.history
+                    .push(collection.remove(card.clone(), *selected));
+                self.reselect(collection);
+
+                self.refresh(&card, language, collection, database, images, session)
+            }
+            Message::QuantityChanged(variant, quantity) => {
+                let State::Showing { card, .. } = &self.state else {
+                    return Task::none();
+                };
+
+                let card = card.clone();
+                let language = collection.language(&card);
+
+                self.history
+                    .push(collection.set_quantity(card.clone(), variant, quantity));
+                self.reselect(collection);
+
+                self.refresh(&card, language, collection, database, images, session)
+            }
+            Message::ToggleWanted => {
+                let State::Showing { card, .. } = &self.state else {
+                    return Task::none();
+                };
+
+                if collection.is_wanted(card) {
+                    collection.unwant(card);
+                } else {
+                    collection.want(card.clone(), None);
+                }
+
+                Task::perform(collection.save(), Message::CollectionSaved).discard()
+            }
+            Message::MaxPriceChanged(new_price) => {
+                let State::Showing {
+                    card, max_price, ..
+                } = &mut self.state
+                else {
+                    return Task::none();
+                };
+
+                // Half-typed prices are kept until they make sense
+                let price = if new_price.trim().is_empty() {
+                    None
+                } else if let Some(price) = pricing::Dollars::parse(&new_price) {
+                    Some(price)
+                } else {
+                    *max_price = new_price;
+                    return Task::none();
+                };
+
+                *max_price = new_price;
+                collection.want(card.clone(), price);
+
+                self.save_later(collection)
+            }
+            Message::CollectorChanged(collector) => {
+                collection.collector = Some(collector).filter(|name| !name.trim().is_empty());
+
+                self.save_later(collection)
+            }
+            Message::ShowWishlist => {
+                self.state = State::Wishlist;
+                self.cancel_downloads(Source::Search);
 
                 Task::none()
             }
+            Message::Undo => self.travel(true, collection, database, images, session),
+            Message::Redo => self.travel(false, collection, database, images, session),
             Message::AddCard(card) => {
                 let State::Adding { variant, .. } = &self.state else {
                     return Task::none();
@@ -321,15 +656,24 @@ impl Binders {
 
                 self.add(card, *variant, collection, database)
             }
-            Message::ImageFetched(card, Ok(image)) => {
-                let _ = self.images.insert(
-                    card.clone(),
-                    Image::Loaded(image::Handle::from_rgba(
-                        image.width,
-                        image.height,
-                        image.rgba,
-                    )),
-                );
+            Message::ImageFetched(card, resolution, Ok(None)) => {
+                let _ = self.downloads.remove(&card);
+
+                // The low resolution image is still good enough
+                if resolution == card::Resolution::Low {
+                    let _ = self.images.insert(card, Image::Missing);
+                }
+
+                Task::none()
+            }
+            Message::ImageFetched(card, resolution, Ok(Some(image))) => {
+                let _ = self.downloads.remove(&card);
+                let _ = self.images.remove(&card);
+                let _ = images.insert(card.clone(), image);
+
+                if resolution == card::Resolution::High {
+                    return Task::none();
+                }
 
                 if let State::Adding { animations, .. } = &mut self.state {
                     animations.insert(card.clone(), AnimationSet::new(now));
@@ -430,6 +774,14 @@ impl Binders {
                 )
             }
             Message::EscapePressed => {
+                if let State::Showing { .. } | State::Importing { .. } | State::Wishlist =
+                    self.state
+                {
+                    self.state = State::Idle;
+
+                    return Task::none();
+                }
+
                 let State::Adding {
                     search, animations, ..
                 } = &mut self.state
@@ -448,18 +800,250 @@ impl Binders {
                 }
 
                 self.state = State::Idle;
+                self.cancel_downloads(Source::Search);
+
+                Task::none()
+            }
+            Message::FileDropped(path) if exchange::Format::from_path(&path).is_some() => {
+                Task::perform(exchange::read(path), Message::FileRead)
+            }
+            Message::FileHovered => {
+                self.drop_target = self.hovered.clone();
+
+                if self.drop_target.as_ref().map(|(card, _)| card) != self.replacing.as_ref() {
+                    self.replacing = None;
+                }
+
+                Task::none()
+            }
+            Message::FilesHoveredLeft => {
+                self.drop_target = None;
+
+                Task::none()
+            }
+            Message::FileDropped(path) => {
+                // Photos dropped on an open card belong to the selected copy
+                if let State::Showing { card, selected, .. } = &self.state
+                    && collection
+                        .cards
+                        .get(card)
+                        .is_some_and(|amount| *selected < amount.copies.len())
+                {
+                    let card = card.clone();
+                    let selected = *selected;
+
+                    return Task::perform(
+                        card::Image::attach_copy(card.clone(), path),
+                        move |result| Message::PhotoAttached(card.clone(), selected, result),
+                    );
+                }
+
+                // Otherwise, they are dropped onto the card they belong to
+                let Some((card, source)) = self.drop_target.clone() else {
+                    self.notice = Some("Drag the photo onto the card it belongs to".to_owned());
+
+                    return Task::none();
+                };
+
+                // Our own photos cannot be downloaded again, so we ask twice
+                if card::Image::is_attached(&card) && self.replacing.as_ref() != Some(&card) {
+                    self.notice = Some(format!(
+                        "Drop it again to replace your photo of {name}",
+                        name = database
+                            .cards
+                            .get(&card)
+                            .map(|card| card.name.as_str())
+                            .unwrap_or(card.as_str())
+                    ));
+                    self.replacing = Some(card);
+
+                    return Task::none();
+                }
+
+                self.replacing = None;
+                self.notice = None;
+
+                Task::perform(card::Image::attach(card.clone(), path), move |result| {
+                    Message::ImageAttached(card.clone(), source, result)
+                })
+            }
+            Message::Export(format) => Task::perform(
+                exchange::export(collection, database, prices, rate, format),
+                Message::Exported,
+            ),
+            Message::Exported(Ok(path)) => {
+                self.notice = Some(format!("Exported to {}", path.display()));
+
+                Task::none()
+            }
+            Message::FileRead(Ok(records)) => {
+                self.state = State::Importing {
+                    report: exchange::resolve(&records, database),
+                };
+                self.cancel_downloads(Source::Search);
+
+                Task::none()
+            }
+            Message::Import => {
+                let State::Importing { report } = &self.state else {
+                    return Task::none();
+                };
+
+                for change in collection.import(&report.matched) {
+                    self.history.push(change);
+                }
+
+                self.notice = Some(format!("Imported {} cards", report.total_cards()));
+                self.state = State::Idle;
+
+                Task::perform(collection.save(), Message::CollectionSaved).discard()
+            }
+            Message::Exported(Err(error)) | Message::FileRead(Err(error)) => {
+                log::error!("{error}");
+
+                self.notice = Some(error.to_string());
+
+                Task::none()
+            }
+            Message::DeletePressed => {
+                let Some((card, source)) = self.hovered.clone() else {
+                    return Task::none();
+                };
+
+                if !card::Image::is_attached(&card) {
+                    return Task::none();
+                }
+
+                // Our own photos cannot be downloaded again, so we ask twice
+                if self.detaching.as_ref() != Some(&card) {
+                    self.notice = Some(format!(
+                        "Press Delete again to remove your photo of {name}",
+                        name = database
+                            .cards
+                            .get(&card)
+                            .map(|card| card.name.as_str())
+                            .unwrap_or(card.as_str())
+                    ));
+                    self.detaching = Some(card);
+
+                    return Task::none();
+                }
+
+                self.detaching = None;
+                self.notice = None;
+
+                Task::perform(card::Image::detach(card.clone()), move |result| {
+                    Message::ImageDetached(card.clone(), source, result)
+                })
+            }
+            pressed @ (Message::PlusPressed | Message::MinusPressed) => {
+                let (State::Idle, Some((card, Source::Binder))) = (&self.state, &self.hovered)
+                else {
+                    return Task::none();
+                };
+
+                let card = card.clone();
+                let language = collection.language(&card);
+                let latest = collection
+                    .cards
+                    .get(&card)
+                    .and_then(|amount| amount.copies.iter().enumerate().next_back())
+                    .map(|(index, copy)| (index, copy.variant));
+
+                let change = match (pressed, latest) {
+                    // Another copy of the latest variant
+                    (Message::PlusPressed, latest) => collection.add(
+                        card.clone(),
+                        latest.map_or(collection::Variant::Normal, |(_, variant)| variant),
+                    ),
+                    (_, Some((index, _))) => collection.remove(card.clone(), index),
+                    (_, None) => return Task::none(),
+                };
+
+                self.history.push(change);
+
+                self.refresh(&card, language, collection, database, images, session)
+            }
+            Message::ImageAttached(card, source, Ok(()))
+            | Message::ImageDetached(card, source, Ok(true)) => {
+                self.cancel_download(&card);
+                images.remove(&card);
+
+                let Some(card) = database.cards.get(&card) else {
+                    return Task::none();
+                };
+
+                let _ = self.images.insert(card.id.clone(), Image::Loading);
+
+                self.download(
+                    card,
+                    card::Resolution::Low,
+                    source,
+                    collection,
+                    database,
+                    session,
+                )
+            }
+            Message::ImageDetached(_, _, Ok(false)) => Task::none(),
+            Message::PhotoAttached(card, index, Ok(photo)) => {
+                let language = collection.language(&card);
+
+                self.history.push(collection.edit(card.clone(), |amount| {
+                    if let Some(copy) = amount.copies.get_mut(index) {
+                        copy.photo = Some(photo);
+                    }
+                }));
+
+                Task::batch([
+                    self.refresh(&card, language, collection, database, images, session),
+                    self.load_photo(collection, database),
+                ])
+            }
+            Message::PhotoLoaded(photo, Ok(image)) => {
+                let _ = self.photos.insert(
+                    photo,
+                    image::Handle::from_rgba(image.width, image.height, image.rgba),
+                );
 
                 Task::none()
             }
+            Message::RemovePhoto => {
+                let State::Showing { card, selected, .. } = &self.state else {
+                    return Task::none();
+                };
+
+                let card = card.clone();
+                let selected = *selected;
+                let language = collection.language(&card);
+
+                // The file is kept, so the removal can be undone
+                self.history.push(collection.edit(card.clone(), |amount| {
+                    if let Some(copy) = amount.copies.get_mut(selected) {
+                        copy.photo = None;
+                    }
+                }));
+
+                self.refresh(&card, language, collection, database, images, session)
+            }
             Message::Tick => Task::none(),
-            Message::ImageFetched(card, Err(error)) => {
+            Message::ImageFetched(card, resolution, Err(error)) => {
                 log::error!("{error}");
 
-                let _ = self.images.insert(card, Image::Errored);
+                let _ = self.downloads.remove(&card);
+
+                // The low resolution image is still good enough
+                if resolution == card::Resolution::Low {
+                    let _ = self.images.insert(card, Image::Errored);
+                }
 
                 Task::none()
             }
-            Message::CollectionSaved(Err(error)) | Message::PriceFetched(_, Err(error)) => {
+            Message::CollectionSaved(Err(error))
+            | Message::PriceFetched(_, Err(error))
+            | Message::ImageAttached(_, _, Err(error))
+            | Message::ImageDetached(_, _, Err(error))
+            | Message::PhotoAttached(_, _, Err(error))
+            | Message::PhotoLoaded(_, Err(error)) => {
                 log::error!("{error}");
 
                 Task::none()
@@ -467,6 +1051,251 @@ impl Binders {
         }
     }
 
+    fn download(
+        &mut self,
+        card: &Card,
+        resolution: card::Resolution,
+        source: Source,
+        collection: &Collection,
+        database: &Database,
+        session: &Session,
+    ) -> Task<Message> {
+        let id = card.id.clone();
+
+        let (task, handle) = Task::perform(
+            card::Image::fetch(
+                card,
+                database,
+                session,
+                resolution,
+                collection.language(&card.id),
+                card::queue::Priority::Visible,
+            ),
+            move |result| Message::ImageFetched(id.clone(), resolution, result),
+        )
+        .abortable();
+
+        let _ = self
+            .downloads
+            .insert(card.id.clone(), (source, handle.abort_on_drop()));
+
+        task
+    }
+
+    fn cancel_download(&mut self, card: &card::Id) {
+        if self.downloads.remove(card).is_some()
+            && matches!(self.images.get(card), Some(Image::Loading))
+        {
+            let _ = self.images.remove(card);
+        }
+    }
+
+    fn cancel_downloads(&mut self, source: Source) {
+        let cancelled: Vec<_> = self
+            .downloads
+            .iter()
+            .filter(|(_, (download, _))| *download == source)
+            .map(|(card, _)| card.clone())
+            .collect();
+
+        for card in cancelled {
+            self.cancel_download(&card);
+        }
+
+        // The cards are gone; they will not notify us when the mouse leaves
+        if self
+            .hovered
+            .as_ref()
+            .is_some_and(|(_, hovered)| *hovered == source)
+        {
+            self.hovered = None;
+        }
+    }
+
+    fn select_copy(&mut self, card: card::Id, index: usize, collection: &Collection) {
+        let copy = collection
+            .cards
+            .get(&card)
+            .and_then(|amount| amount.copies.get(index));
+
+        self.state = State::Showing {
+            grade: copy
+                .and_then(|copy| copy.grade)
+                .map(|grade| grade.to_string())
+                .unwrap_or_default(),
+            acquired: copy
+                .and_then(|copy| copy.acquired)
+                .map(|date| date.to_string())
+                .unwrap_or_default(),
+            max_price: collection
+                .wishlist
+                .get(&card)
+                .and_then(|wish| wish.max_price)
+                .map(|price| format!("{:.2}", price.amount()))
+                .unwrap_or_default(),
+            card,
+            selected: index,
+        };
+    }
+
+    fn travel(
+        &mut self,
+        is_undo: bool,
+        collection: &mut Collection,
+        database: &Database,
+        images: &mut card::Store,
+        session: &Session,
+    ) -> Task<Message> {
+        let card = if is_undo {
+            self.history.next_undo()
+        } else {
+            self.history.next_redo()
+        };
+
+        let Some(card) = card.cloned() else {
+            return Task::none();
+        };
+
+        let language = collection.language(&card);
+
+        if is_undo {
+            let _ = self.history.undo(collection);
+        } else {
+            let _ = self.history.redo(collection);
+        }
+
+        self.reselect(collection);
+        self.refresh(&card, language, collection, database, images, session)
+    }
+
+    fn load_photo(&self, collection: &Collection, database: &Database) -> Task<Message> {
+        let State::Showing { card, selected, .. } = &self.state else {
+            return Task::none();
+        };
+
+        let Some(photo) = collection
+            .cards
+            .get(card)
+            .and_then(|amount| amount.copies.get(*selected))
+            .and_then(|copy| copy.photo.clone())
+        else {
+            return Task::none();
+        };
+
+        if self.photos.contains_key(&photo) {
+            return Task::none();
+        }
+
+        let Some(card) = database.cards.get(card) else {
+            return Task::none();
+        };
+
+        Task::perform(card::Image::open(card, &photo), move |result| {
+            Message::PhotoLoaded(photo.clone(), result)
+        })
+    }
+
+    fn reselect(&mut self, collection: &Collection) {
+        let State::Showing { card, selected, .. } = &self.state else {
+            return;
+        };
+
+        let total = collection
+            .cards
+            .get(card)
+            .map(collection::Amount::total)
+            .unwrap_or_default();
+
+        self.select_copy(
+            card.clone(),
+            (*selected).min(total.saturating_sub(1)),
+            collection,
+        );
+    }
+
+    // Typing saves once the user pauses, instead of on every keystroke
+    fn save_later(&mut self, collection: &Collection) -> Task<Message> {
+        let save = collection.save();
+
+        let (task, handle) = Task::perform(
+            async move {
+                time::sleep(milliseconds(500)).await;
+                save.await
+            },
+            Message::CollectionSaved,
+        )
+        .abortable();
+
+        self.saving = Some(handle.abort_on_drop());
+
+        task.discard()
+    }
+
+    // Saves any change still waiting for the user to pause; e.g. before
+    // the window is closed
+    pub fn flush(&mut self, collection: &Collection) -> Task<Message> {
+        if self.saving.take().is_none() {
+            return Task::none();
+        }
+
+        Task::perform(collection.save(), Message::CollectionSaved).discard()
+    }
+
+    // Saves the collection after the copies of a card changed, fetching its
+    // image again if the language it should be shown in is different now.
+    fn refresh(
+        &mut self,
+        card: &card::Id,
+        language: Option<card::Language>,
+        collection: &Collection,
+        database: &Database,
+        images: &card::Store,
+        session: &Session,
+    ) -> Task<Message> {
+        let save = Task::perform(collection.save(), Message::CollectionSaved).discard();
+
+        if collection.language(card) == language || images.contains(card, collection.language(card))
+        {
+            return save;
+        }
+
+        let Some(card) = database.cards.get(card) else {
+            return save;
+        };
+
+        self.cancel_download(&card.id);
+
+        let _ = self.images.insert(card.id.clone(), Image::Loading);
+        let _ = self.animations.remove(&card.id);
+
+        let download = self.download(
+            card,
+            card::Resolution::Low,
+            Source::Binder,
+            collection,
+            database,
+            session,
+        );
+
+        Task::batch([save, download])
+    }
+
+    fn visible_cards<'a>(&self, collection: &Collection, database: &'a Database) -> Vec<&'a Card> {
+        let Some(pair) = self.binders.open(self.spread) else {
+            return Vec::new();
+        };
+
+        [pair.left, pair.right]
+            .into_iter()
+            .filter_map(|surface| match surface {
+                binder::Surface::Content(content) => Some(content.range),
+                binder::Surface::Cover => None,
+            })
+            .flatten()
+            .filter_map(|i| self.mode.card(i, collection, database))
+            .collect()
+    }
+
     pub fn add(
         &mut self,
         card: card::Id,
@@ -475,13 +1304,14 @@ impl Binders {
         database: &Database,
     ) -> Task<Message> {
         self.state = State::Idle;
+        self.cancel_downloads(Source::Search);
 
         if let Some(position) = self.mode.position(&card, database) {
             self.spread = self.binders.spread(self.binders.place(position));
             let _ = self.animations.remove(&card);
         }
 
-        collection.add(card, variant);
+        self.history.push(collection.add(card, variant));
 
         Task::perform(collection.save(), Message::CollectionSaved).discard()
     }
@@ -490,7 +1320,9 @@ impl Binders {
         &'a self,
         collection: &'a Collection,
         database: &'a Database,
+        images: &'a card::Store,
         prices: &pricing::Map,
+        rate: pricing::ExchangeRate,
         now: Instant,
     ) -> Element<'a, Message> {
         let Some(pair) = self.binders.open(self.spread) else {
@@ -516,17 +1348,42 @@ impl Binders {
                     .into()
             }
 
-            let progress = {
-                let total_cards = self.mode.total_cards(database);
-
-                stat(
+            let progress = match self.mode {
+                binder::Mode::GottaCatchEmAll => {
+                    let total_cards = self.mode.total_cards(collection, database);
+
+                    stat(
+                        pokeball(12),
+                        format!(
+                            "{owned_pokemon} / {total_cards} ({completion:.1}%)",
+                            owned_pokemon = collection.total_pokemon(database),
+                            completion = self.mode.progress(collection, database),
+                        ),
+                    )
+                }
+                binder::Mode::Trade => {
+                    let surplus = collection.surplus(database);
+
+                    let copies: usize = surplus.iter().map(|(_, amount)| amount.total()).sum();
+                    let value =
+                        prices.value(surplus.iter().map(|(card, amount)| (card, amount)), rate);
+
+                    stat(
+                        pokeball(12),
+                        format!(
+                            "{copies} tradeable ({dollars} · {euros})",
+                            dollars = value.america,
+                            euros = value.europe
+                        ),
+                    )
+                }
+                binder::Mode::Recent => stat(
                     pokeball(12),
                     format!(
-                        "{owned_pokemon} / {total_cards} ({completion:.1}%)",
-                        owned_pokemon = collection.total_pokemon(database),
-                        completion = self.mode.progress(collection, database),
+                        "{cards} recently added",
+                        cards = self.mode.total_cards(collection, database)
                     ),
-                )
+                ),
             };
 
             let binders = stat(
@@ -549,6 +1406,15 @@ impl Binders {
                 .padding([5, 10])
                 .text_size(12);
 
+            let language = pick_list(
+                card::Language::ALL,
+                collection.language,
+                Message::LanguageSelected,
+            )
+            .placeholder("Language")
+            .padding([5, 10])
+            .text_size(12);
+
             let add = button(
                 row![
                     icon::add().size(12).height(Fill).center(),
@@ -560,16 +1426,58 @@ impl Binders {
             .on_press(Message::Add(collection::Variant::Normal))
             .padding([0, 10]);
 
-            let controls = row![mode, add].spacing(10).height(Shrink).align_y(Center);
-
-            row![
-                controls,
-                horizontal_space(),
-                row![progress, binders, pages].spacing(30).align_y(Center)
+            let history = row![
+                button(text("Undo").size(12))
+                    .on_press_maybe(self.history.can_undo().then_some(Message::Undo))
+                    .padding([5, 10])
+                    .style(button::secondary),
+                button(text("Redo").size(12))
+                    .on_press_maybe(self.history.can_redo().then_some(Message::Redo))
+                    .padding([5, 10])
+                    .style(button::secondary),
             ]
-            .height(30)
-            .align_y(Center)
-            .spacing(20)
+            .spacing(5);
+
+            let export = pick_list(
+                exchange::Format::ALL,
+                None::<exchange::Format>,
+                Message::Export,
+            )
+            .placeholder("Export")
+            .padding([5, 10])
+            .text_size(12);
+
+            let wishlist = button(text!("Wishlist ({})", collection.wishlist.len()).size(12))
+                .on_press(Message::ShowWishlist)
+                .padding([5, 10])
+                .style(button::secondary);
+
+            let collector = text_input(
+                "Who is collecting?",
+                collection.collector.as_deref().unwrap_or_default(),
+            )
+            .on_input(Message::CollectorChanged)
+            .padding([5, 10])
+            .size(12)
+            .width(140);
+
+            let controls = row![mode, language, add, history, wishlist, export, collector]
+                .spacing(10)
+                .height(Shrink)
+                .align_y(Center);
+
+            let notice = self
+                .notice
+                .as_deref()
+                .map(|notice| text(notice).size(12).style(text::secondary));
+
+            row![controls]
+                .push_maybe(notice)
+                .push(horizontal_space())
+                .push(row![progress, binders, pages].spacing(30).align_y(Center))
+                .height(30)
+                .align_y(Center)
+                .spacing(20)
         };
 
         let left_page = match pair.left {
@@ -584,16 +1492,28 @@ impl Binders {
                 .align_x(Center),
             )
             .into(),
-            binder::Surface::Content(content) => {
-                self.page(pair.binder, content, collection, database, prices, now)
-            }
+            binder::Surface::Content(content) => self.page(
+                pair.binder,
+                content,
+                collection,
+                database,
+                images,
+                prices,
+                now,
+            ),
         };
 
         let right_page = match pair.right {
             binder::Surface::Cover => horizontal_space().into(),
-            binder::Surface::Content(content) => {
-                self.page(pair.binder, content, collection, database, prices, now)
-            }
+            binder::Surface::Content(content) => self.page(
+                pair.binder,
+                content,
+                collection,
+                database,
+                images,
+                prices,
+                now,
+            ),
         };
 
         let content = column![header, row![left_page, right_page].spacing(20)]
@@ -615,9 +1535,31 @@ impl Binders {
                 animations,
                 collection,
                 database,
+                images,
                 prices,
                 now,
             )),
+            State::Showing {
+                card,
+                selected,
+                grade,
+                acquired,
+                max_price,
+            } => database.cards.get(card).map(|card| {
+                showing(
+                    card,
+                    *selected,
+                    grade,
+                    acquired,
+                    max_price,
+                    collection,
+                    database,
+                    images,
+                    &self.photos,
+                )
+            }),
+            State::Importing { report } => Some(importing(report, database)),
+            State::Wishlist => Some(wishlist(collection, database, prices, rate)),
         };
 
         let has_overlay = overlay.is_some();
@@ -646,29 +1588,46 @@ impl Binders {
         content: binder::Content,
         collection: &Collection,
         database: &'a Database,
+        images: &'a card::Store,
         prices: &pricing::Map,
         now: Instant,
     ) -> Element<'a, Message> {
-        let total = self.mode.total_cards(database);
+        let total = self.mode.total_cards(collection, database);
 
         center_y(
             grid(content.range.map(|i| {
                 self.mode
                     .card(i, collection, database)
                     .map(|card| {
-                        item(
+                        let item = item(
                             card,
+                            images
+                                .best(&card.id, collection.language(&card.id))
+                                .map(|(handle, _)| handle),
                             self.images.get(&card.id),
                             self.animations.get(&card.id),
                             prices.get(&card.id),
                             database,
                             now,
                             Source::Binder,
-                        )
+                        );
+
+                        match self.mode {
+                            binder::Mode::GottaCatchEmAll | binder::Mode::Recent => item,
+                            binder::Mode::Trade => {
+                                let surplus = collection.surplus(database);
+
+                                let Some((_, amount)) = surplus.get(i) else {
+                                    return item;
+                                };
+
+                                stack![item, right(trade_tag(amount)).padding(5)].into()
+                            }
+                        }
                     })
                     .unwrap_or_else(|| {
                         if i < total {
-                            placeholder(i)
+                            placeholder(i, self.mode.wanted(i, collection, database))
                         } else {
                             unused_slot()
                         }
@@ -689,6 +1648,7 @@ impl Binders {
         animations: &'a HashMap<card::Id, AnimationSet>,
         collection: &'a Collection,
         database: &'a Database,
+        images: &'a card::Store,
         prices: &pricing::Map,
         now: Instant,
     ) -> Element<'a, Message> {
@@ -759,9 +1719,36 @@ impl Binders {
                             .padding(5)
                         };
 
+                        let wanted_tag = |wish: &collection::Wish| {
+                            container(
+                                container(
+                                    text(match wish.max_price {
+                                        Some(price) => format!("Wanted ≤ {price}"),
+                                        None => "Wanted".to_owned(),
+                                    })
+                                    .size(10),
+                                )
+                                .padding(5)
+                                .style(|theme: &Theme| {
+                                    let palette = theme.extended_palette();
+
+                                    container::Style {
+                                        text_color: Some(palette.primary.base.text),
+                                        ..container::Style::default()
+                                            .background(palette.primary.base.color)
+                                            .border(border::rounded(8))
+                                    }
+                                }),
+                            )
+                            .padding(5)
+                        };
+
                         stack![
                             container(item(
                                 card,
+                                images
+                                    .best(&card.id, collection.language(&card.id))
+                                    .map(|(handle, _)| handle),
                                 self.images.get(&card.id),
                                 animations.get(&card.id),
                                 prices.get(&card.id),
@@ -772,6 +1759,7 @@ impl Binders {
                             .padding(1)
                         ]
                         .push_maybe(collection.cards.get(&card.id).map(owned_tag))
+                        .push_maybe(collection.wishlist.get(&card.id).map(wanted_tag))
                         .into()
                     }))
                     .fluid(300)
@@ -802,6 +1790,14 @@ impl Binders {
                     shift: modifiers.shift(),
                 },
                 Key::Named(Named::Enter) => Message::EnterPressed,
+                Key::Named(Named::Delete) if modifiers.is_empty() => Message::DeletePressed,
+                Key::Character("z" | "Z") if modifiers.command() && modifiers.shift() => {
+                    Message::Redo
+                }
+                Key::Character("z") if modifiers.command() => Message::Undo,
+                Key::Character("y") if modifiers.command() => Message::Redo,
+                Key::Character("+" | "=") if !modifiers.command() => Message::PlusPressed,
+                Key::Character("-") if modifiers.is_empty() => Message::MinusPressed,
                 Key::Character("a") if modifiers.is_empty() => {
                     Message::Add(collection::Variant::Normal)
                 }
@@ -832,21 +1828,29 @@ impl Binders {
             }
         };
 
-        Subscription::batch([hotkeys, animation])
+        let file_drops = event::listen_with(|event, _status, _window| match event {
+            Event::Window(window::Event::FileHovered(_)) => Some(Message::FileHovered),
+            Event::Window(window::Event::FilesHoveredLeft) => Some(Message::FilesHoveredLeft),
+            Event::Window(window::Event::FileDropped(path)) => Some(Message::FileDropped(path)),
+            _ => None,
+        });
+
+        Subscription::batch([hotkeys, file_drops, animation])
     }
 }
 
 fn item<'a>(
     card: &'a Card,
-    thumbnail: Option<&'a Image>,
+    thumbnail: Option<&'a image::Handle>,
+    status: Option<&'a Image>,
     animations: Option<&'a AnimationSet>,
     price: Option<card::Pricing>,
     database: &'a Database,
     now: Instant,
     source: Source,
 ) -> Element<'a, Message> {
-    let item: Element<_> = match thumbnail {
-        Some(Image::Loaded(handle)) => {
+    let item: Element<_> = match (thumbnail, status) {
+        (Some(handle), _) => {
             let (opacity, scale, shadow) = if let Some(animations) = animations {
                 (
                     animations.fade_in.interpolate(0.0, 1.0, now),
@@ -898,7 +1902,15 @@ fn item<'a>(
                         .spread()
                         .map(|spread| typewriter(spread.average.to_string()).size(7));
 
-                    row![].push_maybe(dollars).push_maybe(euros).spacing(8)
+                    let missing = price
+                        .missing
+                        .then(|| typewriter("No price data").size(7).very_quick());
+
+                    row![]
+                        .push_maybe(dollars)
+                        .push_maybe(euros)
+                        .push_maybe(missing)
+                        .spacing(8)
                 });
 
                 let stats: Element<_> = if shadow == 1.0 {
@@ -957,39 +1969,527 @@ fn item<'a>(
                 card.into()
             }
         }
-        Some(Image::Errored) => slot(center(
-            card.name
-                .get("en")
-                .map(text)
-                .or_else(|| {
-                    card.name
-                        .get("ja")
-                        .map(|name| text(name).shaping(text::Shaping::Advanced))
+        // Cards without an image still deserve a face
+        (None, Some(Image::Missing | Image::Errored)) => mouse_area(
+            button(card_face(card, database))
+                .on_press_with(move || match source {
+                    Source::Binder => Message::ShowCard(card.id.clone()),
+                    Source::Search => Message::AddCard(card.id.clone()),
                 })
-                .unwrap_or_else(|| text("Unknown"))
-                .center()
-                .size(14),
-        )),
+                .padding(0)
+                .style(button::text),
+        )
+        .on_enter(Message::CardHovered(card.id.clone(), source, true))
+        .on_exit(Message::CardHovered(card.id.clone(), source, false))
+        .into(),
         _ => slot(horizontal_space()),
     };
 
     pop(item)
         .key(card.id.as_str())
         .on_show(move |_size| Message::CardShown(card.id.clone(), source))
+        .on_hide(Message::CardHidden(card.id.clone(), source))
         .into()
 }
 
-fn placeholder<'a>(index: usize) -> Element<'a, Message> {
-    slot(
-        bottom_right(text!("#{}", index + 1).style(|theme: &Theme| {
-            let palette = theme.extended_palette();
+fn wishlist<'a>(
+    collection: &'a Collection,
+    database: &'a Database,
+    prices: &pricing::Map,
+    rate: pricing::ExchangeRate,
+) -> Element<'a, Message> {
+    let wishes = prices.wishlist(collection, rate);
 
-            text::Style {
-                color: Some(palette.background.weak.color),
+    if wishes.is_empty() {
+        return center(
+            container(text("Your wishlist is empty. Open any card to add it!"))
+                .padding(10)
+                .style(container::bordered_box),
+        )
+        .into();
+    }
+
+    let rows = wishes.into_iter().map(|(card, wish, price)| {
+        let name = database
+            .cards
+            .get(card)
+            .map(|card| card.name.as_str())
+            .unwrap_or(card.as_str());
+
+        let target = match wish.max_price {
+            Some(target) => format!("Up to {target}"),
+            None => "Any price".to_owned(),
+        };
+
+        let status = match (price, wish.max_price) {
+            (Some(price), Some(target)) if price <= target => {
+                text!("{price} · Within budget").style(text::success)
             }
+            (Some(price), Some(target)) => text!(
+                "{price} · {percent:.0}% above target",
+                percent = (price.amount() / target.amount() - 1.0) * 100.0
+            )
+            .style(text::secondary),
+            (Some(price), None) => text!("{price}"),
+            (None, _) => text("No price data").style(text::secondary),
+        };
+
+        button(
+            row![
+                column![
+                    text(name).size(14).shaping(text::Shaping::Advanced),
+                    text!("#{number}", number = card.number()).size(10)
+                ]
+                .spacing(2)
+                .width(Fill),
+                text(target).size(12).width(120),
+                status.size(12).width(220),
+            ]
+            .spacing(10)
+            .align_y(Center),
+        )
+        .on_press_with(move || Message::ShowCard(card.clone()))
+        .width(Fill)
+        .style(button::secondary)
+        .into()
+    });
+
+    center(
+        column![
+            text("Wishlist").size(24),
+            scrollable(column(rows).spacing(5)).height(Shrink),
+        ]
+        .spacing(20)
+        .max_width(800),
+    )
+    .padding(20)
+    .into()
+}
+
+fn importing<'a>(report: &'a exchange::Report, database: &'a Database) -> Element<'a, Message> {
+    let describe = |card: &card::Id| match database.cards.get(card) {
+        Some(card) => format!(
+            "{name} ({set} #{number})",
+            name = card.name.as_str(),
+            set = card.set,
+            number = card.id.number()
+        ),
+        None => card.as_str().to_owned(),
+    };
+
+    let section = |title: String, lines: Vec<Element<'a, Message>>| -> Element<'a, Message> {
+        column![text(title).size(16)]
+            .extend(lines)
+            .spacing(5)
+            .into()
+    };
+
+    let ambiguous = report
+        .ambiguous
+        .iter()
+        .map(|(line, candidates)| {
+            column![
+                text!("{line}").size(12).shaping(text::Shaping::Advanced),
+                text!(
+                    "Could be {}",
+                    candidates
+                        .iter()
+                        .map(&describe)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+                .size(12)
+                .style(text::secondary)
+                .shaping(text::Shaping::Advanced),
+            ]
+            .spacing(2)
+            .into()
+        })
+        .collect();
+
+    let unmatched = report
+        .unmatched
+        .iter()
+        .map(|line| {
+            text!("{line}")
+                .size(12)
+                .shaping(text::Shaping::Advanced)
+                .into()
+        })
+        .collect();
+
+    let total = report.total_cards();
+
+    let mut details: Vec<Element<'a, Message>> = Vec::new();
+
+    if !report.ambiguous.is_empty() {
+        details.push(section(
+            format!("{} ambiguous rows were skipped", report.ambiguous.len()),
+            ambiguous,
+        ));
+    }
+
+    if !report.unmatched.is_empty() {
+        details.push(section(
+            format!("{} rows did not match any card", report.unmatched.len()),
+            unmatched,
+        ));
+    }
+
+    let actions = row![
+        button(text("Cancel").size(14))
+            .on_press(Message::Close)
+            .style(button::secondary),
+        button(text!("Import {total} cards").size(14))
+            .on_press_maybe((total > 0).then_some(Message::Import)),
+    ]
+    .spacing(10);
+
+    center(
+        column![
+            text("Import").size(24),
+            text!(
+                "{total} cards from {rows} rows will be added to the collection.",
+                rows = report.matched.len()
+            )
+            .size(14),
+            scrollable(column(details).spacing(20)).height(Shrink),
+            actions,
+        ]
+        .spacing(20)
+        .max_width(600),
+    )
+    .padding(20)
+    .into()
+}
+
+fn showing<'a>(
+    card: &'a Card,
+    selected: usize,
+    grade: &'a str,
+    acquired: &'a str,
+    max_price: &'a str,
+    collection: &'a Collection,
+    database: &'a Database,
+    images: &'a card::Store,
+    photos: &'a HashMap<PathBuf, image::Handle>,
+) -> Element<'a, Message> {
+    let copies = collection
+        .cards
+        .get(&card.id)
+        .map(|amount| amount.copies.as_slice())
+        .unwrap_or_default();
+
+    // A photo of the selected copy beats any artwork
+    let photo = copies
+        .get(selected)
+        .and_then(|copy| copy.photo.as_ref())
+        .and_then(|photo| photos.get(photo));
+
+    let art: Element<_> = match photo.or_else(|| {
+        images
+            .best(&card.id, collection.language(&card.id))
+            .map(|(handle, _)| handle)
+    }) {
+        Some(handle) => image(handle)
+            .height(Fill)
+            .content_fit(ContentFit::Contain)
+            .into(),
+        None => card_face(card, database),
+    };
+
+    let title = column![
+        text(card.name.as_str())
+            .size(24)
+            .shaping(text::Shaping::Advanced),
+        text!(
+            "{set} #{number}",
+            set = database
+                .sets
+                .get(&card.set)
+                .map(|set| set.name.as_str())
+                .unwrap_or(card.set.as_str()),
+            number = card.id.number()
+        )
+        .size(12)
+        .shaping(text::Shaping::Advanced),
+    ]
+    .spacing(5);
+
+    let quantities = {
+        let amount = collection.cards.get(&card.id);
+
+        row(collection::Variant::ALL.iter().map(|&variant| {
+            let quantity = amount
+                .map(|amount| amount.count(variant))
+                .unwrap_or_default();
+
+            row![
+                text!("{variant}").size(12),
+                button(text("-").size(12).center())
+                    .on_press_maybe(
+                        (quantity > 0).then(|| Message::QuantityChanged(variant, quantity - 1))
+                    )
+                    .width(24)
+                    .style(button::secondary),
+                text!("{quantity}").size(12),
+                button(text("+").size(12).center())
+                    .on_press(Message::QuantityChanged(variant, quantity + 1))
+                    .width(24)
+                    .style(button::secondary),
+            ]
+            .spacing(10)
+            .align_y(Center)
+            .into()
         }))
-        .padding([5, 8]),
+        .spacing(30)
+    };
+
+    let wish = {
+        let is_wanted = collection.is_wanted(&card.id);
+
+        let toggle = button(
+            text(if is_wanted {
+                "Remove from wishlist"
+            } else {
+                "Add to wishlist"
+            })
+            .size(12),
+        )
+        .on_press(Message::ToggleWanted)
+        .style(if is_wanted {
+            button::secondary
+        } else {
+            button::primary
+        });
+
+        let max_price = is_wanted.then(|| {
+            row![
+                text("Up to $").size(12),
+                text_input("Any price", max_price)
+                    .on_input(Message::MaxPriceChanged)
+                    .size(12)
+                    .width(100),
+            ]
+            .spacing(5)
+            .align_y(Center)
+        });
+
+        row![toggle]
+            .push_maybe(max_price)
+            .spacing(20)
+            .align_y(Center)
+    };
+
+    let Some(copy) = copies.get(selected) else {
+        return center(
+            row![
+                container(art).max_width(400),
+                column![
+                    title,
+                    quantities,
+                    wish,
+                    text("You do not own this card.").size(14)
+                ]
+                .spacing(20)
+            ]
+            .spacing(20),
+        )
+        .padding(20)
+        .into();
+    };
+
+    let remove = button(text("Remove copy").size(12))
+        .on_press(Message::RemoveCopy)
+        .style(button::danger);
+
+    let list = column(copies.iter().enumerate().map(|(index, copy)| {
+        button(text!("#{number} {copy}", number = index + 1).size(12))
+            .on_press(Message::CopySelected(index))
+            .width(Fill)
+            .style(if index == selected {
+                button::primary
+            } else {
+                button::secondary
+            })
+            .into()
+    }))
+    .spacing(5);
+
+    let editor = {
+        fn field<'a>(
+            label: &'a str,
+            input: impl Into<Element<'a, Message>>,
+        ) -> Element<'a, Message> {
+            row![text(label).size(12).width(100), input.into()]
+                .spacing(10)
+                .align_y(Center)
+                .into()
+        }
+
+        fn edit<T>(edit: fn(T) -> Edit) -> impl Fn(T) -> Message {
+            move |value| Message::CopyEdited(edit(value))
+        }
+
+        column![
+            field(
+                "Variant",
+                pick_list(
+                    collection::Variant::ALL,
+                    Some(copy.variant),
+                    edit(Edit::Variant)
+                )
+                .text_size(12)
+            ),
+            field(
+                "Condition",
+                pick_list(
+                    collection::Condition::ALL,
+                    Some(copy.condition),
+                    edit(Edit::Condition)
+                )
+                .text_size(12)
+            ),
+            field(
+                "Language",
+                pick_list(card::Language::ALL, copy.language, edit(Edit::Language))
+                    .placeholder("Unknown")
+                    .text_size(12)
+            ),
+            field(
+                "Grading",
+                row![
+                    pick_list(collection::Company::ALL, copy.company, edit(Edit::Company))
+                        .placeholder("Ungraded")
+                        .text_size(12),
+                    text_input("Grade", grade)
+                        .on_input(edit(Edit::Grade))
+                        .size(12)
+                        .width(80),
+                ]
+                .spacing(10)
+            ),
+            field(
+                "Acquired",
+                text_input("YYYY-MM-DD", acquired)
+                    .on_input(edit(Edit::Acquired))
+                    .size(12)
+                    .width(120)
+            ),
+            field(
+                "Notes",
+                text_input("Signed, misprint...", &copy.notes)
+                    .on_input(edit(Edit::Notes))
+                    .size(12)
+            ),
+            field(
+                "Photo",
+                if copy.photo.is_some() {
+                    Element::from(
+                        button(text("Remove photo").size(12))
+                            .on_press(Message::RemovePhoto)
+                            .style(button::secondary),
+                    )
+                } else {
+                    text("Drop an image here to attach it")
+                        .size(12)
+                        .style(text::secondary)
+                        .into()
+                }
+            ),
+        ]
+        .spacing(10)
+    };
+
+    center(
+        row![
+            container(art).max_width(400),
+            column![
+                title,
+                quantities,
+                wish,
+                scrollable(list).height(Shrink),
+                container(column![editor, remove].spacing(10))
+                    .padding(10)
+                    .style(container::bordered_box)
+            ]
+            .spacing(20)
+            .width(Fill)
+        ]
+        .spacing(20)
+        .max_width(1000),
     )
+    .padding(20)
+    .into()
+}
+
+fn trade_tag<'a>(amount: &collection::Amount) -> Element<'a, Message> {
+    let variants = collection::Variant::ALL
+        .iter()
+        .filter_map(|&variant| {
+            let count = amount.count(variant);
+
+            (count > 0).then(|| format!("{count} {variant}"))
+        })
+        .collect::<Vec<_>>()
+        .join(" · ");
+
+    container(text!("Trade {variants}").size(10))
+        .padding(5)
+        .style(|_theme| {
+            container::Style::default()
+                .background(Color::BLACK.scale_alpha(0.8))
+                .border(border::rounded(8))
+        })
+        .into()
+}
+
+fn placeholder<'a>(index: usize, wanted: Option<&'a Card>) -> Element<'a, Message> {
+    let number = bottom_right(text!("#{}", index + 1).style(|theme: &Theme| {
+        let palette = theme.extended_palette();
+
+        text::Style {
+            color: Some(palette.background.weak.color),
+        }
+    }))
+    .padding([5, 8]);
+
+    let Some(card) = wanted else {
+        return slot(number);
+    };
+
+    // Wanted cards are highlighted, so they are easy to spot while trading
+    button(stack![
+        center(
+            column![
+                text("Wanted").size(10).style(text::primary),
+                text(card.name.as_str())
+                    .size(12)
+                    .center()
+                    .shaping(text::Shaping::Advanced),
+            ]
+            .spacing(5)
+            .align_x(Center)
+        )
+        .padding(5),
+        number
+    ])
+    .on_press_with(move || Message::ShowCard(card.id.clone()))
+    .padding(0)
+    .style(|theme, status| {
+        let palette = theme.extended_palette();
+
+        button::Style {
+            border: border::rounded(8)
+                .color(palette.primary.base.color)
+                .width(match status {
+                    button::Status::Hovered => 2,
+                    _ => 1,
+                }),
+            ..button::Style::default().with_background(palette.background.weak.color)
+        }
+    })
+    .into()
 }
 
 fn slot<'a>(content: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
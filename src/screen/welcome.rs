@@ -9,12 +9,13 @@ use crate::widget::logo;
 use function::Binary;
 use iced::animation;
 use iced::border;
+use iced::futures::FutureExt;
 use iced::futures::stream::FuturesOrdered;
 use iced::gradient;
 use iced::time::{Instant, milliseconds, seconds};
 use iced::widget::{
-    bottom_center, button, center, column, container, float, image, mouse_area, row, stack, text,
-    text_input, vertical_space,
+    bottom_center, button, center, column, container, float, horizontal_space, image, mouse_area,
+    pick_list, row, stack, text, text_input, vertical_space,
 };
 use iced::window;
 use iced::{
@@ -24,21 +25,46 @@ use iced_palace::widget::dynamic_text;
 
 pub struct Welcome {
     state: State,
+    cache: Cache,
+}
+
+enum Cache {
+    Idle,
+    Clearing,
+    Cleared(u64),
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     Listed(Result<Vec<Collection>, anywho::Error>),
-    ImagesLoaded(collection::Name, Vec<Result<card::Image, anywho::Error>>),
+    ImagesLoaded(
+        collection::Name,
+        Vec<(card::Id, Result<Option<card::Image>, anywho::Error>)>,
+    ),
     Hovered(collection::Name, bool),
     Select(Collection),
     New,
     NameChanged(String),
     Create(collection::Name),
     Created(Result<Collection, anywho::Error>),
+    Manage(collection::Name, Operation),
+    OperationChanged(Operation),
+    Confirm,
+    Cancel,
+    Managed(Result<(), anywho::Error>),
+    ClearCache,
+    CacheCleared(Result<u64, anywho::Error>),
     Tick,
 }
 
+#[derive(Debug, Clone)]
+pub enum Operation {
+    Rename(String),
+    Duplicate(String),
+    Merge(Option<collection::Name>),
+    Delete,
+}
+
 pub enum State {
     Loading,
     Selection {
@@ -48,6 +74,13 @@ pub enum State {
         name: String,
         collections: Vec<Collection>,
     },
+    Managing {
+        collection: Collection,
+        operation: Operation,
+        collections: Vec<Entry>,
+        is_busy: bool,
+        error: Option<String>,
+    },
 }
 
 pub enum Action {
@@ -61,6 +94,7 @@ impl Welcome {
         (
             Self {
                 state: State::Loading,
+                cache: Cache::Idle,
             },
             Task::perform(Collection::list(), Message::Listed),
         )
@@ -70,6 +104,7 @@ impl Welcome {
         &mut self,
         message: Message,
         database: &Database,
+        images: &mut card::Store,
         prices: &pricing::Map,
         session: &Session,
         now: Instant,
@@ -89,7 +124,21 @@ impl Welcome {
                             prices
                                 .most_expensive(collection, database)
                                 .take(8)
-                                .map(|card| card::Image::fetch(card, database, session)),
+                                .map(|card| {
+                                    let id = card.id.clone();
+
+                                    // Previews should not delay the cards in the binders,
+                                    // nor do they need the full resolution
+                                    card::Image::fetch(
+                                        card,
+                                        database,
+                                        session,
+                                        card::Resolution::Low,
+                                        collection.language(&card.id),
+                                        card::queue::Priority::Background,
+                                    )
+                                    .map(move |result| (id, result))
+                                }),
                         ))
                         .collect()
                         .map(Message::ImagesLoaded.with(collection.name.clone()))
@@ -102,25 +151,36 @@ impl Welcome {
                     Action::Run(load_images)
                 }
             }
-            Message::ImagesLoaded(collection, images) => {
+            Message::ImagesLoaded(collection, loaded) => {
                 let Some(entry) = self.entry_mut(&collection) else {
                     return Action::None;
                 };
 
-                let Ok(images): Result<Vec<_>, _> = images.into_iter().collect() else {
+                let Ok(loaded) = loaded
+                    .into_iter()
+                    .map(|(card, image)| Ok((card, image?)))
+                    .collect::<Result<Vec<_>, anywho::Error>>()
+                else {
                     return Action::None;
                 };
 
-                let images: Vec<_> = images
+                // Cards without an image are left out of the preview
+                let loaded: Vec<_> = loaded
                     .into_iter()
-                    .map(|image| image::Handle::from_rgba(image.width, image.height, image.rgba))
+                    .filter_map(|(card, image)| Some((card, image?)))
                     .collect();
 
-                if images.is_empty() {
+                if loaded.is_empty() {
                     return Action::None;
                 }
 
-                entry.images = images;
+                entry.images = loaded
+                    .into_iter()
+                    .map(|(card, image)| {
+                        let _ = images.insert(card.clone(), image);
+                        card
+                    })
+                    .collect();
                 entry.fade_in.go_mut(true, now);
                 entry.current.go_mut(1.0, now);
 
@@ -166,6 +226,149 @@ impl Welcome {
 
                 Action::Run(Task::perform(Collection::list(), Message::Listed))
             }
+            Message::Manage(name, operation) => {
+                let State::Selection { collections } = &mut self.state else {
+                    return Action::None;
+                };
+
+                let Some(collection) = collections
+                    .iter()
+                    .find(|entry| entry.collection.name == name)
+                    .map(|entry| entry.collection.clone())
+                else {
+                    return Action::None;
+                };
+
+                self.state = State::Managing {
+                    collection,
+                    operation,
+                    collections: std::mem::take(collections),
+                    is_busy: false,
+                    error: None,
+                };
+
+                Action::None
+            }
+            Message::OperationChanged(new_operation) => {
+                if let State::Managing {
+                    operation, error, ..
+                } = &mut self.state
+                {
+                    *operation = new_operation;
+                    *error = None;
+                }
+
+                Action::None
+            }
+            Message::Confirm => {
+                let State::Managing {
+                    collection,
+                    operation,
+                    collections,
+                    is_busy,
+                    ..
+                } = &mut self.state
+                else {
+                    return Action::None;
+                };
+
+                let collection = collection.clone();
+
+                let task = match operation {
+                    Operation::Rename(name) => {
+                        let Some(name) = collection::Name::parse(name) else {
+                            return Action::None;
+                        };
+
+                        Task::perform(
+                            collection.rename(name).map(|result| result.map(|_| ())),
+                            Message::Managed,
+                        )
+                    }
+                    Operation::Duplicate(name) => {
+                        let Some(name) = collection::Name::parse(name) else {
+                            return Action::None;
+                        };
+
+                        Task::perform(
+                            collection.duplicate(name).map(|result| result.map(|_| ())),
+                            Message::Managed,
+                        )
+                    }
+                    Operation::Merge(target) => {
+                        let Some(target) = collections
+                            .iter()
+                            .find(|entry| Some(&entry.collection.name) == target.as_ref())
+                        else {
+                            return Action::None;
+                        };
+
+                        Task::perform(
+                            target
+                                .collection
+                                .clone()
+                                .merge(collection)
+                                .map(|result| result.map(|_| ())),
+                            Message::Managed,
+                        )
+                    }
+                    Operation::Delete => Task::perform(collection.delete(), Message::Managed),
+                };
+
+                *is_busy = true;
+
+                Action::Run(task)
+            }
+            Message::Cancel => {
+                let State::Managing { collections, .. } = &mut self.state else {
+                    return Action::None;
+                };
+
+                self.state = State::Selection {
+                    collections: std::mem::take(collections),
+                };
+
+                Action::None
+            }
+            Message::Managed(Ok(())) => {
+                self.state = State::Loading;
+
+                Action::Run(Task::perform(Collection::list(), Message::Listed))
+            }
+            Message::Managed(Err(error)) => {
+                log::error!("{error}");
+
+                if let State::Managing {
+                    is_busy,
+                    error: message,
+                    ..
+                } = &mut self.state
+                {
+                    *is_busy = false;
+                    *message = Some(error.to_string());
+                }
+
+                Action::None
+            }
+            Message::ClearCache => {
+                self.cache = Cache::Clearing;
+
+                Action::Run(Task::perform(card::cache::clear(), Message::CacheCleared))
+            }
+            Message::CacheCleared(Ok(freed)) => {
+                log::info!("Cleared {freed} bytes of cached images");
+
+                self.cache = Cache::Cleared(freed);
+
+                Action::None
+            }
+            Message::CacheCleared(Err(error)) => {
+                log::error!("{error}");
+
+                self.cache = Cache::Idle;
+
+                Action::None
+            }
             Message::Listed(Err(error)) | Message::Created(Err(error)) => {
                 log::error!("{error}");
 
@@ -188,7 +391,9 @@ impl Welcome {
     }
 
     fn entry_mut(&mut self, name: &collection::Name) -> Option<&mut Entry> {
-        let State::Selection { collections, .. } = &mut self.state else {
+        let (State::Selection { collections, .. } | State::Managing { collections, .. }) =
+            &mut self.state
+        else {
             return None;
         };
 
@@ -197,26 +402,54 @@ impl Welcome {
             .find(|entry| &entry.collection.name == name)
     }
 
-    pub fn view(
-        &self,
+    pub fn view<'a>(
+        &'a self,
         database: &Database,
+        images: &'a card::Store,
         prices: &pricing::Map,
         rate: pricing::ExchangeRate,
         now: Instant,
-    ) -> Element<Message> {
+    ) -> Element<'a, Message> {
         let content: Element<_> = match &self.state {
             State::Loading => text("Loading...").height(512).center().into(),
             State::Selection { collections } => column![
-                row(collections
-                    .iter()
-                    .map(|entry| card(entry, database, prices, rate, now)))
+                row(collections.iter().map(|entry| {
+                    column![
+                        card(entry, database, images, prices, rate, now),
+                        actions(&entry.collection, collections.len() > 1)
+                    ]
+                    .spacing(10)
+                    .align_x(Center)
+                    .into()
+                }))
                 .spacing(30),
-                button(
-                    row![icon::add().size(14), text("New Profile").size(14)]
+                row![
+                    button(
+                        row![icon::add().size(14), text("New Profile").size(14)]
+                            .spacing(10)
+                            .align_y(Center)
+                    )
+                    .on_press(Message::New),
+                    button(
+                        row![
+                            icon::cancel().size(14),
+                            text(match self.cache {
+                                Cache::Idle => "Clear Cache".to_owned(),
+                                Cache::Clearing => "Clearing...".to_owned(),
+                                Cache::Cleared(freed) => format!("Freed {}", size(freed)),
+                            })
+                            .size(14)
+                        ]
                         .spacing(10)
                         .align_y(Center)
-                )
-                .on_press(Message::New),
+                    )
+                    .style(button::secondary)
+                    .on_press_maybe(
+                        // The cache fills up again as cards are viewed
+                        (!matches!(self.cache, Cache::Clearing)).then_some(Message::ClearCache)
+                    ),
+                ]
+                .spacing(10),
             ]
             .spacing(30)
             .align_x(Center)
@@ -234,7 +467,11 @@ impl Welcome {
                     .on_input(Message::NameChanged)
                     .padding(10);
 
-                let name = collection::Name::parse(name);
+                let name = collection::Name::parse(name).filter(|name| {
+                    !collections
+                        .iter()
+                        .any(|collection| collection.name.collides(name))
+                });
 
                 let submit = button(if collections.is_empty() {
                     "Start"
@@ -252,6 +489,19 @@ impl Welcome {
                 .max_width(600)
                 .into()
             }
+            State::Managing {
+                collection,
+                operation,
+                collections,
+                is_busy,
+                error,
+            } => manage(
+                collection,
+                operation,
+                collections,
+                *is_busy,
+                error.as_deref(),
+            ),
         };
 
         stack![
@@ -280,6 +530,7 @@ impl Welcome {
 fn card<'a>(
     entry: &'a Entry,
     database: &Database,
+    store: &'a card::Store,
     prices: &pricing::Map,
     rate: pricing::ExchangeRate,
     now: Instant,
@@ -354,19 +605,27 @@ fn card<'a>(
         let current = current.interpolate_with(|value| value, now) + 1.0;
         let fade_in = fade_in.interpolate(0.0, 1.0, now);
 
+        // Evicted images are simply skipped until the next visit
+        let preview = |index: usize, opacity: f32| -> Element<'a, Message> {
+            match store.get(
+                &images[index % images.len()],
+                collection.language(&images[index % images.len()]),
+                card::Resolution::Low,
+            ) {
+                Some(handle) => container(
+                    image(handle)
+                        .content_fit(ContentFit::Cover)
+                        .opacity(opacity),
+                )
+                .padding(1)
+                .into(),
+                None => vertical_space().into(),
+            }
+        };
+
         stack![
-            container(
-                image(&images[(current as usize - 1) % images.len()])
-                    .content_fit(ContentFit::Cover)
-                    .opacity(fade_in * (1.0 - current.fract()))
-            )
-            .padding(1),
-            container(
-                image(&images[current as usize % images.len()])
-                    .content_fit(ContentFit::Cover)
-                    .opacity(fade_in * current.fract())
-            )
-            .padding(1),
+            preview(current as usize - 1, fade_in * (1.0 - current.fract())),
+            preview(current as usize, fade_in * current.fract()),
             container(content).padding(20).style(move |_theme| {
                 container::Style::default()
                     .background(
@@ -403,6 +662,147 @@ fn card<'a>(
         .into()
 }
 
+fn actions(collection: &Collection, can_merge: bool) -> Element<'_, Message> {
+    let action = |label, operation: Operation| {
+        button(text(label).size(12))
+            .on_press(Message::Manage(collection.name.clone(), operation))
+            .style(button::text)
+    };
+
+    row![
+        action(
+            "Rename",
+            Operation::Rename(collection.name.as_str().to_owned())
+        ),
+        action(
+            "Duplicate",
+            Operation::Duplicate(format!("{} (copy)", collection.name))
+        ),
+    ]
+    .push_maybe(can_merge.then(|| action("Merge", Operation::Merge(None))))
+    .push(action("Delete", Operation::Delete))
+    .spacing(5)
+    .into()
+}
+
+fn manage<'a>(
+    collection: &'a Collection,
+    operation: &'a Operation,
+    collections: &'a [Entry],
+    is_busy: bool,
+    error: Option<&'a str>,
+) -> Element<'a, Message> {
+    let name = collection.name.as_str();
+
+    // A renamed collection may only change the case of its own name
+    let is_available = |new_name: &str, is_renaming: bool| {
+        collection::Name::parse(new_name).is_some_and(|new_name| {
+            !collections.iter().any(|entry| {
+                (!is_renaming || entry.collection.name != collection.name)
+                    && entry.collection.name.collides(&new_name)
+            })
+        })
+    };
+
+    let (prompt, input, confirm): (String, Element<'a, Message>, _) = match operation {
+        Operation::Rename(new_name) => (
+            format!("What should {name} be called from now on?"),
+            text_input("Name", new_name)
+                .on_input(|name| Message::OperationChanged(Operation::Rename(name)))
+                .padding(10)
+                .into(),
+            button("Rename")
+                .on_press_maybe(is_available(new_name, true).then_some(Message::Confirm)),
+        ),
+        Operation::Duplicate(new_name) => (
+            format!("{name} will be copied into a new collection."),
+            text_input("Name", new_name)
+                .on_input(|name| Message::OperationChanged(Operation::Duplicate(name)))
+                .padding(10)
+                .into(),
+            button("Duplicate")
+                .on_press_maybe(is_available(new_name, false).then_some(Message::Confirm)),
+        ),
+        Operation::Merge(target) => {
+            let targets: Vec<_> = collections
+                .iter()
+                .map(|entry| entry.collection.name.clone())
+                .filter(|target| target != &collection.name)
+                .collect();
+
+            (
+                match target {
+                    Some(target) => format!(
+                        "Every card of {name} will be moved to {target}, \
+                        and {name} will be deleted. This cannot be undone!"
+                    ),
+                    None => format!("Which collection should the cards of {name} be moved to?"),
+                },
+                pick_list(targets, target.clone(), |target| {
+                    Message::OperationChanged(Operation::Merge(Some(target)))
+                })
+                .placeholder("Collection")
+                .padding(10)
+                .into(),
+                button("Merge")
+                    .style(button::danger)
+                    .on_press_maybe(target.is_some().then_some(Message::Confirm)),
+            )
+        }
+        Operation::Delete => (
+            format!(
+                "{name} and its {total} cards will be deleted. This cannot be undone!",
+                total = collection.total_cards()
+            ),
+            horizontal_space().into(),
+            button("Delete")
+                .style(button::danger)
+                .on_press(Message::Confirm),
+        ),
+    };
+
+    let prompt = container(text(prompt))
+        .width(Fill)
+        .padding(10)
+        .style(container::bordered_box);
+
+    let cancel = button("Cancel")
+        .padding([10, 20])
+        .style(button::secondary)
+        .on_press_maybe((!is_busy).then_some(Message::Cancel));
+
+    let confirm = if is_busy {
+        confirm.on_press_maybe(None)
+    } else {
+        confirm
+    }
+    .padding([10, 20]);
+
+    column![prompt, row![input, cancel, confirm].spacing(10)]
+        .push_maybe(error.map(|error| text(error).size(14).style(text::danger)))
+        .spacing(30)
+        .max_width(600)
+        .into()
+}
+
+fn size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} {}", UNITS[unit])
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
 fn legal_disclaimer<'a>() -> Element<'a, Message> {
     bottom_center(
         text(
@@ -422,7 +822,7 @@ fn legal_disclaimer<'a>() -> Element<'a, Message> {
 
 pub struct Entry {
     collection: Collection,
-    images: Vec<image::Handle>,
+    images: Vec<card::Id>,
     fade_in: Animation<bool>,
     current: Animation<f32>,
     zoom: Animation<bool>,
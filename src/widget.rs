@@ -1,5 +1,8 @@
-use iced::widget::{canvas, text};
-use iced::{Element, Pixels};
+use crate::pokebase::card::Type;
+use crate::pokebase::{Card, Database};
+
+use iced::widget::{canvas, center, column, container, horizontal_space, row, text};
+use iced::{Center, Color, Element, Fill, Pixels, border, color};
 
 pub fn logo<'a, Message>(size: impl Into<Pixels>) -> Element<'a, Message> {
     const PKG_NAME: &str = env!("CARGO_PKG_NAME");
@@ -68,3 +71,120 @@ pub fn pokeball<'a, Message: 'a>(size: impl Into<Pixels>) -> Element<'a, Message
 
     canvas(Pokeball).width(size).height(size).into()
 }
+
+// A stand-in for the image of a card, drawn only from the database.
+pub fn card_face<'a, Message: 'a>(card: &'a Card, database: &'a Database) -> Element<'a, Message> {
+    use iced::gradient;
+    use iced::{Degrees, Font, font};
+
+    let (primary, secondary) = {
+        let mut types = card.types.iter().copied().map(type_color);
+        let primary = types.next().unwrap_or(color!(0xb0aca4));
+
+        (primary, types.next().unwrap_or(primary))
+    };
+
+    let panel = |_theme: &_| {
+        container::Style::default()
+            .background(Color::WHITE.scale_alpha(0.75))
+            .border(border::rounded(4))
+    };
+
+    let header = {
+        let name = text(card.name.as_str())
+            .size(12)
+            .font(Font {
+                weight: font::Weight::Bold,
+                ..Font::DEFAULT
+            })
+            .shaping(text::Shaping::Advanced)
+            .color(Color::BLACK);
+
+        let types = row(card.types.iter().map(|type_| {
+            container(text(type_.to_string()[..1].to_owned()).size(8).center())
+                .width(12)
+                .height(12)
+                .style(move |_theme| {
+                    container::Style::default()
+                        .background(type_color(*type_))
+                        .border(border::rounded(6).color(Color::WHITE).width(1))
+                        .color(Color::WHITE)
+                })
+                .into()
+        }))
+        .spacing(2);
+
+        container(row![name, horizontal_space(), types].align_y(Center))
+            .padding([2, 6])
+            .style(panel)
+    };
+
+    let art = container(center(pokeball(32)).style(|_theme| {
+        container::Style::default()
+            .background(Color::BLACK.scale_alpha(0.15))
+            .border(border::rounded(4))
+    }))
+    .height(Fill);
+
+    let illustrator = card
+        .illustrator
+        .as_deref()
+        .map(|illustrator| text!("Illus. {illustrator}").size(7).color(Color::BLACK));
+
+    let footer = {
+        let set = database
+            .sets
+            .get(&card.set)
+            .map(|set| set.name.as_str())
+            .unwrap_or(card.set.as_str());
+
+        container(
+            row![
+                text!("{set} #{number}", number = card.id.number())
+                    .size(7)
+                    .shaping(text::Shaping::Advanced),
+                horizontal_space(),
+                text(card.rarity.to_string()).size(7),
+            ]
+            .spacing(5),
+        )
+        .padding([2, 6])
+        .style(move |theme| panel(theme).color(Color::BLACK))
+    };
+
+    container(
+        column![header, art]
+            .push_maybe(illustrator)
+            .push(footer)
+            .spacing(4),
+    )
+    .padding(8)
+    .width(Fill)
+    .height(Fill)
+    .style(move |_theme| {
+        container::Style::default()
+            .background(
+                gradient::Linear::new(Degrees(135.0))
+                    .add_stop(0.0, primary)
+                    .add_stop(1.0, secondary),
+            )
+            .border(border::rounded(12).color(color!(0xf2cf3e)).width(4))
+    })
+    .into()
+}
+
+fn type_color(type_: Type) -> Color {
+    match type_ {
+        Type::Grass => color!(0x5da34a),
+        Type::Fire => color!(0xe4572e),
+        Type::Water => color!(0x3b8fd8),
+        Type::Lightning => color!(0xf2c230),
+        Type::Psychic => color!(0x9b59b6),
+        Type::Fighting => color!(0xc0713a),
+        Type::Darkness => color!(0x2e3a46),
+        Type::Metal => color!(0x8e9aa6),
+        Type::Fairy => color!(0xe88fbf),
+        Type::Dragon => color!(0xb8962e),
+        Type::Colorless => color!(0xd9d4c7),
+    }
+}